@@ -0,0 +1,986 @@
+// Import heap related library from `alloc`
+// https://doc.rust-lang.org/alloc/index.html
+use alloc::vec::Vec;
+
+use ckb_std::{ckb_constants::Source, error::SysError, high_level};
+
+use crate::{hash, merkle};
+
+pub const SHANNONS_PER_CKB: u64 = 100_000_000;
+
+// The creator has to get the next milestone approved and released within this many epochs
+// after its time (about 7 days), otherwise the project fails.
+pub const MILESTONE_GRACE_EPOCHS: u64 = 42;
+
+// Anyone can challenge a vote tally with the proof of a wrong leaf within this many epochs
+// after it is submitted (about 3 days), then it is confirmed.
+pub const TALLY_CHALLENGE_EPOCHS: u64 = 18;
+// In the veto mode, the backers can object to a milestone within this many epochs after its time (about 3 days),
+// then it is released unless the objections have reached the veto threshold.
+pub const VETO_WINDOW_EPOCHS: u64 = 18;
+// The arbitrator can decide on a milestone still not released this many epochs after its time (about 3 days),
+// when the backers have not approved it.
+pub const ARBITRATION_EPOCHS: u64 = 18;
+// In the commit-reveal voting, the backers reveal their votes within this many epochs after the time of the milestone
+// (about 3 days), then the votes revealed are tallied.
+pub const REVEAL_WINDOW_EPOCHS: u64 = 18;
+
+#[derive(Debug)]
+pub struct MilestoneInfo {
+    pub time: u64,                    // epoch, from the c-cell
+    pub amount: u128,                 // give it to creator, shannons or the base unit of the UDT
+    pub approval_ratio_threshold: u8, // 0 ~ 10, e.g. 6 means 60% of the votes in favor can be passed
+    pub quorum: u8, // 0 ~ 10, e.g. 3 means the votes cast must weigh 30% of the total pledged
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    AllOrNothing, // the pledges are refunded if the pledge threshold is not reached
+    KeepItAll, // the creator keeps whatever is raised by the pledge time, except the milestone holdbacks
+}
+
+#[derive(Debug)]
+pub struct CrowdfundingInfo {
+    pub pledge_time: u64,               // epoch, from the c-cell
+    pub pledge_threshold: u128, // threshold for starting a project, shannons or the base unit of the UDT
+    pub startup_amount: u128,   // start-up capital for creators
+    pub milestones: Vec<MilestoneInfo>, // milestones
+    pub currency: Option<[u8; 32]>, // type script hash of the UDT raised, None means CKB
+    pub mode: Mode,
+    pub hard_cap: u128,               // the maximum raise
+    pub min_pledge: u128,             // the minimum pledged by a backer
+    pub max_pledge: u128,             // the maximum pledged by a backer
+    pub allow_list: Option<[u8; 32]>, // Merkle root of the sender lock hashes allowed to pledge
+    pub kyc_issuer: Option<[u8; 20]>, // blake160 of the public key attesting the backers
+    pub platform_fee: Option<PlatformFee>,
+    pub payees: Vec<Payee>, // the releases are split among the payees, empty means the creator takes all
+    pub vesting: bool, // the milestones stream linearly from the release before them instead of lump sums
+    pub vote_type: Option<VoteType>, // the vote cells counted against the approval ratio thresholds
+    pub veto_threshold: Option<u8>, // 1 ~ 10, the milestones are released unless the objections reach it
+    pub council: Option<Council>,   // the council approving the milestones instead of the backers
+    pub arbitrator: Option<[u8; 20]>, // the lock hash of the arbitrator of the disputed milestones
+    pub quadratic: bool, // the votes weigh the integer square roots of the pledges instead of the pledges
+    pub commit_reveal: bool, // only the votes revealed from the commitments made before the milestones count
+}
+
+// basis points of the whole
+const BASIS_POINTS: u16 = 10_000;
+
+#[derive(Debug)]
+pub struct PlatformFee {
+    pub operator: [u8; 20], // the operator lock hash 160 receiving the fee
+    pub rate: u16,          // basis points of every release, 0 ~ 10000
+}
+
+#[derive(Debug)]
+pub struct Payee {
+    pub lock_hash: [u8; 20], // the payee lock hash 160
+    pub weight: u16,         // the share of the payee is weight / the sum of the weights
+}
+
+#[derive(Debug)]
+pub struct Council {
+    pub threshold: u8, // the number of the members signing a milestone to approve it
+    pub members: Vec<[u8; 20]>, // blake160 of the public keys of the members
+}
+
+impl Council {
+    // The hash of the project id and the milestone index signed by the council members
+    pub fn message(project_id: &[u8], milestone: u8) -> [u8; 32] {
+        let mut data = Vec::with_capacity(33);
+        data.extend_from_slice(project_id);
+        data.push(milestone);
+        hash::blake2b_256(&data)
+    }
+}
+
+#[derive(Debug)]
+pub struct VoteType {
+    pub code_hash: [u8; 32], // the code hash of the vote type script
+    pub hash_type: u8,
+}
+
+impl PlatformFee {
+    // fee = floor(amount * rate / 10000)
+    pub fn fee_of(&self, amount: u128) -> Option<u128> {
+        amount
+            .checked_mul(self.rate as u128)
+            .map(|amount| amount / BASIS_POINTS as u128)
+    }
+}
+
+// The project data of the version 0 starts with the pledge time, whose first byte is always 0
+const VERSION_0: u8 = 0;
+const VERSION_1: u8 = 1;
+const VERSION_2: u8 = 2;
+
+const MAX_MILESTONES: usize = 10;
+
+// fields of the version 2
+const FIELD_CURRENCY: u8 = 1;
+const FIELD_MODE: u8 = 2;
+const FIELD_HARD_CAP: u8 = 3;
+const FIELD_MIN_PLEDGE: u8 = 4;
+const FIELD_MAX_PLEDGE: u8 = 5;
+const FIELD_ALLOW_LIST: u8 = 6;
+const FIELD_KYC_ISSUER: u8 = 7;
+const FIELD_PLATFORM_FEE: u8 = 8;
+const FIELD_PAYEES: u8 = 9;
+const FIELD_VESTING: u8 = 10;
+const FIELD_VOTE_TYPE: u8 = 11;
+const FIELD_QUORUMS: u8 = 12;
+const FIELD_VETO_THRESHOLD: u8 = 13;
+const FIELD_COUNCIL: u8 = 14;
+const FIELD_ARBITRATOR: u8 = 15;
+const FIELD_QUADRATIC: u8 = 16;
+const FIELD_COMMIT_REVEAL: u8 = 17;
+
+const PAYEE_LEN: usize = 22;
+
+impl CrowdfundingInfo {
+    pub fn try_from(cell_dep_index: usize) -> Result<Self, SysError> {
+        let data = high_level::load_cell_data(cell_dep_index, Source::CellDep)?;
+        let crowdfunding_info = match data.first() {
+            Some(&VERSION_0) if data.len() >= 16 => Self::from_version_0(&data),
+            Some(&VERSION_1) if data.len() >= 41 => Self::from_version_1(&data),
+            Some(&VERSION_2) if data.len() >= 42 => Self::from_version_2(&data)?,
+            _ => return Err(SysError::Encoding),
+        };
+        if crowdfunding_info.milestones.len() > MAX_MILESTONES {
+            return Err(SysError::Encoding);
+        }
+        Ok(crowdfunding_info)
+    }
+
+    // The project without any of the optional fields of the version 2
+    fn new(
+        pledge_time: u64,
+        pledge_threshold: u128,
+        startup_amount: u128,
+        milestones: Vec<MilestoneInfo>,
+        currency: Option<[u8; 32]>,
+    ) -> Self {
+        CrowdfundingInfo {
+            pledge_time,
+            pledge_threshold,
+            startup_amount,
+            milestones,
+            currency,
+            mode: Mode::AllOrNothing,
+            hard_cap: u128::MAX,
+            min_pledge: 0,
+            max_pledge: u128::MAX,
+            allow_list: None,
+            kyc_issuer: None,
+            platform_fee: None,
+            payees: Vec::new(),
+            vesting: false,
+            vote_type: None,
+            veto_threshold: None,
+            council: None,
+            arbitrator: None,
+            quadratic: false,
+            commit_reveal: false,
+        }
+    }
+
+    // The amounts are u32 in CKB for the CKB campaigns and in the base unit for the UDT campaigns
+    fn from_version_0(data: &[u8]) -> Self {
+        let (milestones_data, currency) = split_currency(&data[16..], 13);
+        let to_amount = |amount: u32| match currency {
+            Some(_) => amount as u128,
+            None => ckb_to_shannons(amount),
+        };
+        let milestones = milestones_data
+            .chunks_exact(13)
+            .map(|milestone| MilestoneInfo {
+                time: as_u64_be(&milestone[0..8]),
+                amount: to_amount(as_u32_be(&milestone[8..12])),
+                approval_ratio_threshold: milestone[12],
+                quorum: 0,
+            })
+            .collect();
+        Self::new(
+            as_u64_be(&data[0..8]),
+            to_amount(as_u32_be(&data[8..12])),
+            to_amount(as_u32_be(&data[12..16])),
+            milestones,
+            currency,
+        )
+    }
+
+    // The amounts are u128 in shannons or in the base unit of the UDT
+    fn from_version_1(data: &[u8]) -> Self {
+        let (milestones_data, currency) = split_currency(&data[41..], 25);
+        Self::new(
+            as_u64_be(&data[1..9]),
+            as_u128_be(&data[9..25]),
+            as_u128_be(&data[25..41]),
+            parse_milestones(milestones_data),
+            currency,
+        )
+    }
+
+    // The version 1 with the number of the milestones, and the optional fields after the milestones
+    fn from_version_2(data: &[u8]) -> Result<Self, SysError> {
+        let fields_start = 42 + data[41] as usize * 25;
+        let milestones_data = data.get(42..fields_start).ok_or(SysError::Encoding)?;
+        let mut crowdfunding_info = Self::new(
+            as_u64_be(&data[1..9]),
+            as_u128_be(&data[9..25]),
+            as_u128_be(&data[25..41]),
+            parse_milestones(milestones_data),
+            None,
+        );
+        // every field is set at most once, the ids of the fields are below 32
+        let mut fields_set = 0u32;
+        let mut fields = &data[fields_start..];
+        while !fields.is_empty() {
+            let len = *fields.get(1).ok_or(SysError::Encoding)? as usize;
+            let value = fields.get(2..2 + len).ok_or(SysError::Encoding)?;
+            let field_bit = 1u32
+                .checked_shl(fields[0] as u32)
+                .ok_or(SysError::Encoding)?;
+            if fields_set & field_bit != 0 {
+                return Err(SysError::Encoding);
+            }
+            fields_set |= field_bit;
+            crowdfunding_info.set_field(fields[0], value)?;
+            fields = &fields[2 + len..];
+        }
+        // the milestones are approved by the council or objected to by the backers, not both
+        if crowdfunding_info.council.is_some() && crowdfunding_info.veto_threshold.is_some() {
+            return Err(SysError::Encoding);
+        }
+        // the votes and the objections weigh the positions in the escrow cells, which vesting projects lack
+        if crowdfunding_info.vesting
+            && (crowdfunding_info.vote_type.is_some() || crowdfunding_info.veto_threshold.is_some())
+        {
+            return Err(SysError::Encoding);
+        }
+        Ok(crowdfunding_info)
+    }
+
+    fn set_field(&mut self, field: u8, value: &[u8]) -> Result<(), SysError> {
+        match (field, value.len()) {
+            (FIELD_CURRENCY, 32) => self.currency = Some(as_hash(value)),
+            (FIELD_MODE, 1) => {
+                self.mode = match value[0] {
+                    0 => Mode::AllOrNothing,
+                    1 => Mode::KeepItAll,
+                    _ => return Err(SysError::Encoding),
+                }
+            }
+            (FIELD_HARD_CAP, 16) => self.hard_cap = as_u128_be(value),
+            (FIELD_MIN_PLEDGE, 16) => self.min_pledge = as_u128_be(value),
+            (FIELD_MAX_PLEDGE, 16) => self.max_pledge = as_u128_be(value),
+            (FIELD_ALLOW_LIST, 32) => self.allow_list = Some(as_hash(value)),
+            (FIELD_KYC_ISSUER, 20) => {
+                let mut kyc_issuer = [0u8; 20];
+                kyc_issuer.copy_from_slice(value);
+                self.kyc_issuer = Some(kyc_issuer);
+            }
+            (FIELD_PLATFORM_FEE, 22) => {
+                let mut operator = [0u8; 20];
+                operator.copy_from_slice(&value[0..20]);
+                let rate = ((value[20] as u16) << 8) + value[21] as u16;
+                if rate > BASIS_POINTS {
+                    return Err(SysError::Encoding);
+                }
+                self.platform_fee = Some(PlatformFee { operator, rate });
+            }
+            (FIELD_PAYEES, len) if len > 0 && len % PAYEE_LEN == 0 => {
+                self.payees = value
+                    .chunks_exact(PAYEE_LEN)
+                    .map(|payee| {
+                        let mut lock_hash = [0u8; 20];
+                        lock_hash.copy_from_slice(&payee[0..20]);
+                        Payee {
+                            lock_hash,
+                            weight: ((payee[20] as u16) << 8) + payee[21] as u16,
+                        }
+                    })
+                    .collect();
+                if self.payees.iter().all(|payee| payee.weight == 0) {
+                    return Err(SysError::Encoding);
+                }
+            }
+            (FIELD_VESTING, 1) if value[0] <= 1 => self.vesting = value[0] == 1,
+            (FIELD_VOTE_TYPE, 33) => {
+                self.vote_type = Some(VoteType {
+                    code_hash: as_hash(value),
+                    hash_type: value[32],
+                })
+            }
+            (FIELD_QUORUMS, len) if len == self.milestones.len() => {
+                for (milestone, quorum) in self.milestones.iter_mut().zip(value) {
+                    if *quorum > 10 {
+                        return Err(SysError::Encoding);
+                    }
+                    milestone.quorum = *quorum;
+                }
+            }
+            (FIELD_VETO_THRESHOLD, 1) if (1..=10).contains(&value[0]) => {
+                self.veto_threshold = Some(value[0])
+            }
+            (FIELD_COUNCIL, len) if len > 1 && (len - 1) % 20 == 0 => {
+                let members = value[1..]
+                    .chunks_exact(20)
+                    .map(|member| {
+                        let mut pubkey_hash = [0u8; 20];
+                        pubkey_hash.copy_from_slice(member);
+                        pubkey_hash
+                    })
+                    .collect::<Vec<_>>();
+                if value[0] == 0 || value[0] as usize > members.len() {
+                    return Err(SysError::Encoding);
+                }
+                self.council = Some(Council {
+                    threshold: value[0],
+                    members,
+                });
+            }
+            (FIELD_ARBITRATOR, 20) => {
+                let mut arbitrator = [0u8; 20];
+                arbitrator.copy_from_slice(value);
+                self.arbitrator = Some(arbitrator);
+            }
+            (FIELD_QUADRATIC, 1) if value[0] <= 1 => self.quadratic = value[0] == 1,
+            (FIELD_COMMIT_REVEAL, 1) if value[0] <= 1 => self.commit_reveal = value[0] == 1,
+            _ => return Err(SysError::Encoding),
+        }
+        Ok(())
+    }
+
+    // The releases go one by one, the startup amount is the release 0 and the n-th milestone is the release n.
+    // Return the epoch since which the release can be made and its amount.
+    pub fn release(&self, release: u8, total_pledged: u128) -> Option<(u64, u128)> {
+        let epoch = self.release_epoch(release)?;
+        let amount = self
+            .released_amount(release.checked_add(1)?, total_pledged)?
+            .checked_sub(self.released_amount(release, total_pledged)?)?;
+        Some((epoch, amount))
+    }
+
+    pub fn release_epoch(&self, release: u8) -> Option<u64> {
+        if release == 0 {
+            Some(self.pledge_time)
+        } else {
+            Some(self.milestones.get(release as usize - 1)?.time)
+        }
+    }
+
+    // The amount of all the releases before the release.
+    // The keep-it-all projects release whatever raised except the milestone holdbacks as the startup amount,
+    // and the milestones as long as the total pledged lasts.
+    pub fn released_amount(&self, release: u8, total_pledged: u128) -> Option<u128> {
+        if release == 0 {
+            return Some(0);
+        }
+        let milestones_amount =
+            sum_milestones_amount(self.milestones.get(..release as usize - 1)?)?;
+        match self.mode {
+            Mode::AllOrNothing => self.startup_amount.checked_add(milestones_amount),
+            Mode::KeepItAll => {
+                let holdbacks = sum_milestones_amount(&self.milestones)?;
+                let startup_amount = total_pledged.saturating_sub(holdbacks);
+                Some(
+                    startup_amount
+                        .checked_add(milestones_amount)?
+                        .min(total_pledged),
+                )
+            }
+        }
+    }
+
+    // The amount vested by the epoch in the vesting projects, the startup amount is released at the pledge time,
+    // then every milestone streams linearly from the epoch of the release before it to its time.
+    pub fn vested_amount(&self, epoch: u64, total_pledged: u128) -> Option<u128> {
+        let releases = self.releases();
+        let release = match (0..releases).find(|release| {
+            self.release_epoch(*release)
+                .map_or(false, |release_epoch| release_epoch > epoch)
+        }) {
+            Some(0) => return Some(0),
+            Some(release) => release,
+            None => return self.released_amount(releases, total_pledged),
+        };
+        let start = self.release_epoch(release - 1)?;
+        let end = self.release_epoch(release)?;
+        let (_, amount) = self.release(release, total_pledged)?;
+        let streamed = amount.checked_mul((epoch - start) as u128)? / (end - start) as u128;
+        self.released_amount(release, total_pledged)?
+            .checked_add(streamed)
+    }
+
+    // The releases completed by the total released, the phase of the vesting projects follows it
+    pub fn completed_releases(&self, total_released: u128, total_pledged: u128) -> Option<u8> {
+        let mut completed = 0;
+        for release in 1..=self.releases() {
+            if self.released_amount(release, total_pledged)? > total_released {
+                break;
+            }
+            completed = release;
+        }
+        Some(completed)
+    }
+
+    // The project fails if the release after the released ones is not made by this epoch
+    pub fn failed_epoch(&self, released: u8) -> Option<u64> {
+        self.release_epoch(released)?
+            .checked_add(MILESTONE_GRACE_EPOCHS)
+    }
+
+    // The votes of a backer weigh the live pledges, or their integer square root in the quadratic voting
+    pub fn vote_weight(&self, pledge_weight: u128) -> u128 {
+        if self.quadratic {
+            isqrt(pledge_weight)
+        } else {
+            pledge_weight
+        }
+    }
+
+    // The weight of all the backers the approval ratios and the quorums are of
+    pub fn total_vote_weight(&self, state_info: &StateInfo) -> u128 {
+        if self.quadratic {
+            state_info.total_weight
+        } else {
+            state_info.total_pledged
+        }
+    }
+
+    // The number of releases of the project, the startup amount and all the milestones
+    pub fn releases(&self) -> u8 {
+        self.milestones.len() as u8 + 1
+    }
+
+    // The amendments move their milestones to the new times, the later ones over the earlier ones
+    pub fn amend(&mut self, amendments: &[Amendment]) {
+        for amendment in amendments {
+            if let Some(milestone) = self.milestones.get_mut(amendment.milestone as usize) {
+                milestone.time = amendment.time;
+            }
+        }
+    }
+}
+
+// version 0:
+// pledge_time 8 bytes, pledge_threshold 4 bytes, startup_amount 4 bytes, milestones: [time 8bytes, amount 4bytes, approval_ratio_threshold 1 byte],
+// currency 32 bytes (optional)
+// version 1:
+// version 1 byte, pledge_time 8 bytes, pledge_threshold 16 bytes, startup_amount 16 bytes,
+// milestones: [time 8bytes, amount 16bytes, approval_ratio_threshold 1 byte], currency 32 bytes (optional)
+// version 2:
+// version 1 byte, pledge_time 8 bytes, pledge_threshold 16 bytes, startup_amount 16 bytes, milestones count 1 byte,
+// milestones: [time 8bytes, amount 16bytes, approval_ratio_threshold 1 byte], fields: [field 1 byte, length 1 byte, value]
+// fields: currency (1) 32 bytes, mode (2) 1 byte (0: all-or-nothing, 1: keep-it-all), hard cap (3) 16 bytes,
+// min pledge (4) 16 bytes, max pledge (5) 16 bytes, allow list (6) 32 bytes, kyc issuer (7) 20 bytes,
+// platform fee (8) 22 bytes: [operator lock hash 20 bytes, rate 2 bytes in basis points],
+// payees (9) 22 bytes each: [payee lock hash 20 bytes, weight 2 bytes], vesting (10) 1 byte (0: lump sums, 1: linear),
+// vote type (11) 33 bytes: [code hash 32 bytes, hash type 1 byte], quorums (12) 1 byte for each milestone,
+// veto threshold (13) 1 byte (1 ~ 10, e.g. 3 means the objections of 30% of the total pledged stop a milestone),
+// council (14): [threshold 1 byte, member public key hashes 20 bytes each], arbitrator (15) lock hash 20 bytes,
+// quadratic (16) 1 byte (0: the votes weigh the pledges, 1: the integer square roots of the pledges),
+// commit-reveal (17) 1 byte (0: public votes, 1: the votes revealed from the commitments)
+
+fn parse_milestones(data: &[u8]) -> Vec<MilestoneInfo> {
+    data.chunks_exact(25)
+        .map(|milestone| MilestoneInfo {
+            time: as_u64_be(&milestone[0..8]),
+            amount: as_u128_be(&milestone[8..24]),
+            approval_ratio_threshold: milestone[24],
+            quorum: 0,
+        })
+        .collect()
+}
+
+fn sum_milestones_amount(milestones: &[MilestoneInfo]) -> Option<u128> {
+    milestones
+        .iter()
+        .try_fold(0u128, |sum, milestone| sum.checked_add(milestone.amount))
+}
+
+// The currency follows the milestones, it is there if the length is not a multiple of the milestone length
+fn split_currency(data: &[u8], milestone_len: usize) -> (&[u8], Option<[u8; 32]>) {
+    if data.len() >= 32 && data.len() % milestone_len == 32 % milestone_len {
+        let (milestones_data, currency_data) = data.split_at(data.len() - 32);
+        (milestones_data, Some(as_hash(currency_data)))
+    } else {
+        (data, None)
+    }
+}
+
+// The integer square root, floor(sqrt(n)) by the Newton's method, which goes down from n to the root
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = n / 2 + n % 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+pub fn ckb_to_shannons(amount: u32) -> u128 {
+    amount as u128 * SHANNONS_PER_CKB as u128
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PledgeInfo {
+    pub released: u8, // releases already taken from the pledge, 1 is the startup amount, n + 1 means n milestones
+    pub settled: u128, // the total released of the project the pledge has paid for, only in the vesting projects
+    pub pledged: u128, // the pledge moved into the escrow cells, the weight of the refunds and the votes
+}
+
+impl PledgeInfo {
+    pub fn try_from(index: usize, source: Source, is_udt: bool) -> Result<Self, SysError> {
+        let data = high_level::load_cell_data(index, source)?;
+        Ok(PledgeInfo::from_slice(&data, is_udt))
+    }
+
+    pub fn from_slice(data: &[u8], is_udt: bool) -> Self {
+        // a pledge cell without data has not been released yet,
+        // the UDT amount comes first in the data of the UDT pledge cells
+        let data = if is_udt {
+            data.get(UDT_AMOUNT_LEN..).unwrap_or_default()
+        } else {
+            data
+        };
+        PledgeInfo {
+            released: data.first().copied().unwrap_or(0),
+            settled: data.get(1..17).map_or(0, as_u128_be),
+            pledged: data.get(17..33).map_or(0, as_u128_be),
+        }
+    }
+}
+
+// CKB pledge: released 1 byte, settled 16 bytes (vesting), pledged 16 bytes (escrow)
+// UDT pledge: UDT amount 16 bytes, released 1 byte, settled 16 bytes (vesting), pledged 16 bytes (escrow)
+
+pub const UDT_AMOUNT_LEN: usize = 16;
+
+// The amount of a UDT cell is the first 16 bytes of the data in little endian
+pub fn udt_amount(data: &[u8]) -> Option<u128> {
+    let mut buf = [0u8; UDT_AMOUNT_LEN];
+    buf.copy_from_slice(data.get(0..UDT_AMOUNT_LEN)?);
+    Some(u128::from_le_bytes(buf))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Pledging,
+    Funded, // the startup amount has been released
+    Failed,
+    Milestone(u8), // the milestone of the index (0-based) has been released
+    Completed,     // the last milestone has been released
+    Cancelled,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StateInfo {
+    pub phase: Phase,
+    pub total_pledged: u128, // amount pledged when the project got funded, shannons or UDT
+    pub total_released: u128, // amount released to the creator, shannons or UDT
+    pub total_weight: u128, // the quadratic weight of the backers when the project got funded, 0 if not quadratic
+    pub funding: Option<[u8; 32]>, // the funding transaction, recorded by the first transition after it
+    pub amendments: Vec<Amendment>, // the milestone times amended by the backers, in order
+}
+
+impl StateInfo {
+    pub fn try_from(index: usize, source: Source) -> Result<Self, SysError> {
+        let data = high_level::load_cell_data(index, source)?;
+        StateInfo::from_slice(&data)
+    }
+
+    pub fn from_slice(data: &[u8]) -> Result<Self, SysError> {
+        // the total weight is there in the quadratic voting or with the funding transaction after it,
+        // which the amendments follow
+        if data.len() != 34
+            && data.len() != 50
+            && (data.len() < 82 || (data.len() - 82) % AMENDMENT_LEN != 0)
+        {
+            return Err(SysError::Encoding);
+        }
+        let phase = match (data[0], data[1]) {
+            (0, _) => Phase::Pledging,
+            (1, _) => Phase::Funded,
+            (2, _) => Phase::Failed,
+            (3, index) => Phase::Milestone(index),
+            (4, _) => Phase::Completed,
+            (5, _) => Phase::Cancelled,
+            _ => return Err(SysError::Encoding),
+        };
+        Ok(StateInfo {
+            phase,
+            total_pledged: as_u128_be(&data[2..18]),
+            total_released: as_u128_be(&data[18..34]),
+            total_weight: data.get(34..50).map_or(0, as_u128_be),
+            funding: data.get(50..82).map(as_hash),
+            amendments: data
+                .get(82..)
+                .unwrap_or_default()
+                .chunks_exact(AMENDMENT_LEN)
+                .map(Amendment::from_slice)
+                .collect(),
+        })
+    }
+
+    // How many releases the project has made, None if the project has failed or been cancelled or the phase overflows
+    pub fn released(&self, releases: u8) -> Option<u8> {
+        match self.phase {
+            Phase::Pledging => Some(0),
+            Phase::Funded => Some(1),
+            Phase::Milestone(index) => index.checked_add(2),
+            Phase::Completed => Some(releases),
+            Phase::Failed | Phase::Cancelled => None,
+        }
+    }
+
+    // The phase of the project after the releases
+    pub fn phase_of(released: u8, releases: u8) -> Phase {
+        if released == 0 {
+            Phase::Pledging
+        } else if released >= releases {
+            Phase::Completed
+        } else if released == 1 {
+            Phase::Funded
+        } else {
+            Phase::Milestone(released - 2)
+        }
+    }
+}
+
+// phase 1 byte (0: pledging, 1: funded, 2: failed, 3: milestone, 4: completed, 5: cancelled), milestone index 1 byte,
+// total_pledged 16 bytes, total_released 16 bytes, total_weight 16 bytes (optional),
+// funding tx hash 32 bytes (optional), amendments (optional)
+
+pub const AMENDMENT_LEN: usize = 41;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Amendment {
+    pub milestone: u8,      // the index of the milestone amended
+    pub time: u64,          // the new epoch of the milestone
+    pub proposal: [u8; 32], // the lock hash of the proposal cell the backers approved
+}
+
+impl Amendment {
+    pub fn from_slice(data: &[u8]) -> Self {
+        Amendment {
+            milestone: data[0],
+            time: as_u64_be(&data[1..9]),
+            proposal: as_hash(&data[9..41]),
+        }
+    }
+}
+
+// milestone index 1 byte, time 8 bytes, proposal 32 bytes
+
+#[derive(Debug, PartialEq)]
+pub struct ReceiptInfo {
+    pub backer: [u8; 20], // the sender lock hash 160 in the args of the pledge lock
+    pub amount: u128,     // amount pledged, shannons or UDT
+}
+
+impl ReceiptInfo {
+    pub fn try_from(index: usize, source: Source) -> Result<Self, SysError> {
+        let data = high_level::load_cell_data(index, source)?;
+        ReceiptInfo::from_slice(&data)
+    }
+
+    pub fn from_slice(data: &[u8]) -> Result<Self, SysError> {
+        if data.len() != 36 {
+            return Err(SysError::Encoding);
+        }
+        let mut backer = [0u8; 20];
+        backer.copy_from_slice(&data[0..20]);
+        Ok(ReceiptInfo {
+            backer,
+            amount: as_u128_be(&data[20..36]),
+        })
+    }
+}
+
+// backer 20 bytes, amount 16 bytes
+
+pub const VOTE_LEN: usize = 38;
+pub const REVEAL_LEN: usize = 70;
+pub const AMENDMENT_VOTE_LEN: usize = 46;
+
+#[derive(Debug, PartialEq)]
+pub struct VoteInfo {
+    pub milestone: u8,    // the index of the milestone voted on
+    pub backer: [u8; 20], // the sender lock hash 160 in the args of the pledge lock
+    pub approve: bool,
+    pub weight: u128, // the pledge of the backer, capped by the live pledges when tallied
+    pub commitment: Option<[u8; 32]>, // the commitment the vote is revealed from
+    pub amendment: Option<u64>, // the new time of the milestone the vote on its amendment is for
+}
+
+impl VoteInfo {
+    pub fn try_from(index: usize, source: Source) -> Result<Self, SysError> {
+        let data = high_level::load_cell_data(index, source)?;
+        VoteInfo::from_slice(&data)
+    }
+
+    pub fn from_slice(data: &[u8]) -> Result<Self, SysError> {
+        if (data.len() != VOTE_LEN && data.len() != REVEAL_LEN && data.len() != AMENDMENT_VOTE_LEN)
+            || data[21] > 1
+        {
+            return Err(SysError::Encoding);
+        }
+        let mut backer = [0u8; 20];
+        backer.copy_from_slice(&data[1..21]);
+        Ok(VoteInfo {
+            milestone: data[0],
+            backer,
+            approve: data[21] == 1,
+            weight: as_u128_be(&data[22..38]),
+            commitment: data.get(38..70).map(as_hash),
+            amendment: match data.len() {
+                AMENDMENT_VOTE_LEN => Some(as_u64_be(&data[38..46])),
+                _ => None,
+            },
+        })
+    }
+}
+
+// milestone index 1 byte, backer 20 bytes, vote 1 byte (0: against, 1: in favor), weight 16 bytes,
+// commitment 32 bytes (only in the revealed votes), time 8 bytes (only in the votes on the amendments)
+
+pub const COMMITMENT_LEN: usize = 53;
+
+#[derive(Debug, PartialEq)]
+pub struct CommitmentInfo {
+    pub milestone: u8,    // the index of the milestone voted on
+    pub backer: [u8; 20], // the sender lock hash 160 in the args of the pledge lock
+    pub commitment: [u8; 32],
+}
+
+impl CommitmentInfo {
+    pub fn from_slice(data: &[u8]) -> Result<Self, SysError> {
+        if data.len() != COMMITMENT_LEN {
+            return Err(SysError::Encoding);
+        }
+        let mut backer = [0u8; 20];
+        backer.copy_from_slice(&data[1..21]);
+        Ok(CommitmentInfo {
+            milestone: data[0],
+            backer,
+            commitment: as_hash(&data[21..53]),
+        })
+    }
+
+    // The hash the backer commits to before the milestone, revealed later with the vote and the salt
+    pub fn commitment_of(
+        project_id: &[u8],
+        milestone: u8,
+        backer: &[u8; 20],
+        approve: bool,
+        salt: &[u8],
+    ) -> [u8; 32] {
+        let mut data = Vec::with_capacity(86);
+        data.extend_from_slice(project_id);
+        data.push(milestone);
+        data.extend_from_slice(backer);
+        data.push(approve as u8);
+        data.extend_from_slice(salt);
+        hash::blake2b_256(&data)
+    }
+}
+
+// milestone index 1 byte, backer 20 bytes, commitment 32 bytes: blake2b of the project id 32 bytes,
+// the milestone index 1 byte, the backer 20 bytes, the vote 1 byte and the salt 32 bytes
+
+pub const DELEGATION_LEN: usize = 40;
+
+#[derive(Debug, PartialEq)]
+pub struct DelegationInfo {
+    pub delegator: [u8; 20], // the sender lock hash 160 of the backer delegating the votes
+    pub delegate: [u8; 20],  // the sender lock hash 160 of the backer voting for it
+}
+
+impl DelegationInfo {
+    pub fn from_slice(data: &[u8]) -> Result<Self, SysError> {
+        if data.len() != DELEGATION_LEN || data[0..20] == data[20..40] {
+            return Err(SysError::Encoding);
+        }
+        let mut delegator = [0u8; 20];
+        delegator.copy_from_slice(&data[0..20]);
+        let mut delegate = [0u8; 20];
+        delegate.copy_from_slice(&data[20..40]);
+        Ok(DelegationInfo {
+            delegator,
+            delegate,
+        })
+    }
+}
+
+// delegator 20 bytes, delegate 20 bytes
+
+pub const TALLY_LEN: usize = 65;
+
+#[derive(Debug, PartialEq)]
+pub struct TallyInfo {
+    pub root: [u8; 32],  // the root hash of the Merkle-sum tree of the votes
+    pub approved: u128,  // the root sum of the votes in favor
+    pub cast: u128,      // the root sum of the votes cast
+    pub confirmed: bool, // the tally has not been challenged within the challenge epochs
+}
+
+impl TallyInfo {
+    pub fn try_from(index: usize, source: Source) -> Result<Self, SysError> {
+        let data = high_level::load_cell_data(index, source)?;
+        TallyInfo::from_slice(&data)
+    }
+
+    pub fn from_slice(data: &[u8]) -> Result<Self, SysError> {
+        if data.len() != TALLY_LEN || data[64] > 1 {
+            return Err(SysError::Encoding);
+        }
+        Ok(TallyInfo {
+            root: as_hash(&data[0..32]),
+            approved: as_u128_be(&data[32..48]),
+            cast: as_u128_be(&data[48..64]),
+            confirmed: data[64] == 1,
+        })
+    }
+
+    pub fn root(&self) -> merkle::SumNode {
+        merkle::SumNode {
+            hash: self.root,
+            approved: self.approved,
+            cast: self.cast,
+        }
+    }
+}
+
+// root hash 32 bytes, root sum of the votes in favor 16 bytes, root sum of the votes cast 16 bytes, confirmed 1 byte
+
+pub const TALLY_LEAF_LEN: usize = 122;
+
+#[derive(Debug)]
+pub struct TallyLeaf {
+    pub backer: [u8; 20], // the sender lock hash 160 in the args of the pledge lock
+    pub approve: bool,
+    pub weight: u128,
+    pub signature: [u8; 65], // signature of the backer
+    pub next: [u8; 20],      // the backer of the next leaf, zeros for the last leaf
+    pub hash: [u8; 32],
+}
+
+impl TallyLeaf {
+    pub fn from_slice(data: &[u8]) -> Result<Self, SysError> {
+        if data.len() != TALLY_LEAF_LEN || data[20] > 1 {
+            return Err(SysError::Encoding);
+        }
+        let mut backer = [0u8; 20];
+        backer.copy_from_slice(&data[0..20]);
+        let mut signature = [0u8; 65];
+        signature.copy_from_slice(&data[37..102]);
+        let mut next = [0u8; 20];
+        next.copy_from_slice(&data[102..122]);
+        Ok(TallyLeaf {
+            backer,
+            approve: data[20] == 1,
+            weight: as_u128_be(&data[21..37]),
+            signature,
+            next,
+            hash: hash::blake2b_256(data),
+        })
+    }
+
+    // the votes against weigh nothing in the sum of the votes in favor
+    pub fn node(&self) -> merkle::SumNode {
+        merkle::SumNode {
+            hash: self.hash,
+            approved: if self.approve { self.weight } else { 0 },
+            cast: self.weight,
+        }
+    }
+
+    // The hash of the vote and the weight of the leaf, signed by its backer
+    pub fn message(&self, project_id: &[u8], milestone: u8) -> [u8; 32] {
+        let mut data = Vec::with_capacity(70);
+        data.extend_from_slice(project_id);
+        data.push(milestone);
+        data.extend_from_slice(&self.backer);
+        data.push(self.approve as u8);
+        data.extend_from_slice(&self.weight.to_be_bytes());
+        hash::blake2b_256(&data)
+    }
+}
+
+// backer 20 bytes, vote 1 byte, weight 16 bytes, signature 65 bytes, next backer 20 bytes
+// the message signed: project id 32 bytes, milestone index 1 byte, backer 20 bytes, vote 1 byte, weight 16 bytes
+
+pub const ATTESTATION_LEN: usize = 93;
+
+#[derive(Debug)]
+pub struct AttestationInfo {
+    pub backer: [u8; 20],    // the sender lock hash 160 attested
+    pub expiry: u64,         // epoch, the attestation is valid until
+    pub signature: [u8; 65], // signature of the issuer
+}
+
+impl AttestationInfo {
+    pub fn from_slice(data: &[u8]) -> Result<Self, SysError> {
+        if data.len() != ATTESTATION_LEN {
+            return Err(SysError::Encoding);
+        }
+        let mut backer = [0u8; 20];
+        backer.copy_from_slice(&data[0..20]);
+        let mut signature = [0u8; 65];
+        signature.copy_from_slice(&data[28..93]);
+        Ok(AttestationInfo {
+            backer,
+            expiry: as_u64_be(&data[20..28]),
+            signature,
+        })
+    }
+
+    // The hash of the attestation signed by the issuer, bound to the project and the issuer
+    pub fn message(&self, project_id: &[u8], issuer: &[u8; 20]) -> [u8; 32] {
+        let mut data = Vec::with_capacity(80);
+        data.extend_from_slice(project_id);
+        data.extend_from_slice(issuer);
+        data.extend_from_slice(&self.backer);
+        data.extend_from_slice(&self.expiry.to_be_bytes());
+        hash::blake2b_256(&data)
+    }
+}
+
+// backer 20 bytes, expiry epoch 8 bytes, signature 65 bytes
+// the message signed: project id 32 bytes, issuer 20 bytes, backer 20 bytes, expiry epoch 8 bytes
+
+pub fn as_u32_be(array: &[u8]) -> u32 {
+    ((array[0] as u32) << 24)
+        + ((array[1] as u32) << 16)
+        + ((array[2] as u32) << 8)
+        + ((array[3] as u32) << 0)
+}
+
+pub fn as_u64_be(array: &[u8]) -> u64 {
+    ((array[0] as u64) << 56)
+        + ((array[1] as u64) << 48)
+        + ((array[2] as u64) << 40)
+        + ((array[3] as u64) << 32)
+        + ((array[4] as u64) << 24)
+        + ((array[5] as u64) << 16)
+        + ((array[6] as u64) << 8)
+        + ((array[7] as u64) << 0)
+}
+
+pub fn as_u128_be(array: &[u8]) -> u128 {
+    let mut buf = [0u8; 16];
+    buf.copy_from_slice(&array[0..16]);
+    u128::from_be_bytes(buf)
+}
+
+pub fn as_hash(array: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&array[0..32]);
+    hash
+}
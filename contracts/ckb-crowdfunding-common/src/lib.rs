@@ -0,0 +1,13 @@
+//! Cell data formats and utilities shared by the crowdfunding scripts.
+//!
+//! See `data.rs` for the data of the c-cell, the pledge cells and the state cell.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod data;
+pub mod hash;
+pub mod merkle;
+pub mod position;
+pub mod since;
@@ -0,0 +1,106 @@
+use alloc::vec::Vec;
+
+use crate::hash::blake2b_256;
+
+// The leaves of the allow-list are the hashes of the sender lock hashes,
+// and every node is the hash of its two children in ascending order, so the proof doesn't need the positions.
+pub fn leaf_hash(lock_hash: &[u8; 20]) -> [u8; 32] {
+    blake2b_256(lock_hash)
+}
+
+// The proof is the siblings from the leaf up to the root, 32 bytes each
+pub fn verify_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[u8]) -> bool {
+    if proof.len() % 32 != 0 {
+        return false;
+    }
+    let node = proof.chunks_exact(32).fold(leaf, |node, sibling| {
+        let mut pair = [0u8; 64];
+        if &node[..] <= sibling {
+            pair[0..32].copy_from_slice(&node);
+            pair[32..64].copy_from_slice(sibling);
+        } else {
+            pair[0..32].copy_from_slice(sibling);
+            pair[32..64].copy_from_slice(&node);
+        }
+        blake2b_256(&pair[..])
+    });
+    &node == root
+}
+
+// The vote tallies are Merkle-sum trees, where every node is the hash of the hashes and the sums of its two children
+// and its sums are theirs added up, so the proof of a leaf proves the sums along the path as well.
+// The sums wrap around, a tree overflowing must have a leaf weighing more than the pledge of its backer.
+pub const SUM_SIBLING_LEN: usize = 65;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SumNode {
+    pub hash: [u8; 32],
+    pub approved: u128, // the weight of the votes in favor
+    pub cast: u128,     // the weight of the votes cast
+}
+
+pub fn sum_node(left: &SumNode, right: &SumNode) -> SumNode {
+    let mut data = [0u8; 128];
+    data[0..32].copy_from_slice(&left.hash);
+    data[32..48].copy_from_slice(&left.approved.to_be_bytes());
+    data[48..64].copy_from_slice(&left.cast.to_be_bytes());
+    data[64..96].copy_from_slice(&right.hash);
+    data[96..112].copy_from_slice(&right.approved.to_be_bytes());
+    data[112..128].copy_from_slice(&right.cast.to_be_bytes());
+    SumNode {
+        hash: blake2b_256(&data[..]),
+        approved: left.approved.wrapping_add(right.approved),
+        cast: left.cast.wrapping_add(right.cast),
+    }
+}
+
+// The tree is built level by level, pairing the nodes in order and moving the last one up if it has no pair
+pub fn sum_root(mut nodes: Vec<SumNode>) -> Option<SumNode> {
+    while nodes.len() > 1 {
+        nodes = nodes
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => sum_node(left, right),
+                _ => pair[0],
+            })
+            .collect();
+    }
+    nodes.pop()
+}
+
+// The proof is the siblings from the leaf up to the root, [side 1 byte (0: left, 1: right), hash 32 bytes,
+// sum of the votes in favor 16 bytes, sum of the votes cast 16 bytes] each, and the sides of the siblings
+// are the position of the leaf.
+// Return the root computed from the leaf, None if the proof is malformed.
+pub fn sum_proof_root(leaf: SumNode, proof: &[u8]) -> Option<SumNode> {
+    if proof.len() % SUM_SIBLING_LEN != 0 {
+        return None;
+    }
+    proof
+        .chunks_exact(SUM_SIBLING_LEN)
+        .try_fold(leaf, |node, sibling| {
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(&sibling[1..33]);
+            let mut approved = [0u8; 16];
+            approved.copy_from_slice(&sibling[33..49]);
+            let mut cast = [0u8; 16];
+            cast.copy_from_slice(&sibling[49..65]);
+            let sibling_node = SumNode {
+                hash,
+                approved: u128::from_be_bytes(approved),
+                cast: u128::from_be_bytes(cast),
+            };
+            match sibling[0] {
+                0 => Some(sum_node(&sibling_node, &node)),
+                1 => Some(sum_node(&node, &sibling_node)),
+                _ => None,
+            }
+        })
+}
+
+// The leaf of the proof is the first one of the tree if all its siblings are on the right
+pub fn is_leftmost(proof: &[u8]) -> bool {
+    proof
+        .chunks_exact(SUM_SIBLING_LEN)
+        .all(|sibling| sibling[0] == 1)
+}
@@ -0,0 +1,214 @@
+use alloc::vec::Vec;
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{
+        packed::{BytesVec, RawTransaction, Script},
+        prelude::*,
+    },
+    error::SysError,
+    high_level::{
+        load_cell_lock, load_cell_type_hash, load_input_out_point, load_transaction, QueryIter,
+    },
+};
+
+use crate::data::{as_hash, Phase, PledgeInfo, StateInfo};
+use crate::hash::blake2b_256;
+
+// Anyone can create a cell of the pledge lock with any data, so the votes weigh only the positions
+// created by the finalize transaction, which is the funding transaction of the escrow projects
+// and checks the pledge recorded by every position.
+// A position moved by transfers is proved by the raw transactions moving it back to the funding transaction,
+// as the pledge lock checks that a transfer carries over the pledges of the positions it consumes.
+
+// the transaction and the index of an output
+pub type OutPoint = ([u8; 32], u32);
+
+// The funding transaction of the project from the state cell in the outputs or the cell deps.
+// The state cell records it from the first transition after the funding,
+// until then the state cell in the cell deps is the one created by the funding transaction.
+pub fn load_funding_tx_hash(state_type_hash: &[u8]) -> Result<Option<[u8; 32]>, SysError> {
+    for source in [Source::Output, Source::CellDep].iter() {
+        let index = match QueryIter::new(load_cell_type_hash, *source)
+            .position(|type_hash| type_hash.as_ref().map(|hash| &hash[..]) == Some(state_type_hash))
+        {
+            Some(index) => index,
+            None => continue,
+        };
+        let state_info = StateInfo::try_from(index, *source)?;
+        if state_info.funding.is_some() || *source == Source::Output {
+            return Ok(state_info.funding);
+        }
+        if state_info.phase != Phase::Funded {
+            return Ok(None);
+        }
+        return Ok(load_cell_dep_out_points()?
+            .get(index)
+            .map(|(tx_hash, _)| *tx_hash));
+    }
+    Ok(None)
+}
+
+// The out points of the cell deps in the order of the source.
+// The cells of a dep group can't be told apart, so they stop at the first dep group.
+pub fn load_cell_dep_out_points() -> Result<Vec<OutPoint>, SysError> {
+    let cell_deps = load_transaction()?.raw().cell_deps();
+    let mut out_points = Vec::with_capacity(cell_deps.len());
+    for index in 0..cell_deps.len() {
+        let cell_dep = cell_deps.get_unchecked(index);
+        if cell_dep.dep_type().as_slice() != [0] {
+            break;
+        }
+        out_points.push(unpack_out_point(&cell_dep.out_point()));
+    }
+    Ok(out_points)
+}
+
+pub fn load_input_out_point_of(index: usize) -> Result<OutPoint, SysError> {
+    Ok(unpack_out_point(&load_input_out_point(
+        index,
+        Source::Input,
+    )?))
+}
+
+fn unpack_out_point(out_point: &ckb_std::ckb_types::packed::OutPoint) -> OutPoint {
+    (
+        as_hash(out_point.tx_hash().as_slice()),
+        out_point.index().unpack(),
+    )
+}
+
+// The proofs of the moved positions are the witnesses after the ones of the inputs,
+// every one the raw transactions from the one creating a position back to the funding transaction.
+pub fn load_position_proofs() -> Result<Vec<Vec<u8>>, SysError> {
+    let tx = load_transaction()?;
+    let inputs_len = tx.raw().inputs().len();
+    Ok(tx
+        .witnesses()
+        .into_iter()
+        .skip(inputs_len)
+        .map(|witness| witness.raw_data().to_vec())
+        .collect())
+}
+
+// The position is created by the funding transaction, or moved from one of its positions by the transactions of a proof
+pub fn is_funded_position<F: Fn(&Script) -> bool>(
+    is_pledge_lock: &F,
+    out_point: &OutPoint,
+    funding_tx_hash: &[u8; 32],
+    proofs: &[Vec<u8>],
+    is_udt: bool,
+) -> bool {
+    &out_point.0 == funding_tx_hash
+        || proofs.iter().any(|proof| {
+            is_moved_position(is_pledge_lock, out_point, funding_tx_hash, proof, is_udt)
+                .unwrap_or(false)
+        })
+}
+
+// Every transaction of the proof creates a position consumed by the one before it
+fn is_moved_position<F: Fn(&Script) -> bool>(
+    is_pledge_lock: &F,
+    out_point: &OutPoint,
+    funding_tx_hash: &[u8; 32],
+    proof: &[u8],
+    is_udt: bool,
+) -> Option<bool> {
+    let mut out_points = alloc::vec![*out_point];
+    for raw_tx in BytesVec::from_slice(proof).ok()?.into_iter() {
+        let raw_tx = RawTransaction::from_slice(&raw_tx.raw_data()).ok()?;
+        let tx_hash = blake2b_256(raw_tx.as_slice());
+        if !out_points.iter().any(|(hash, index)| {
+            hash == &tx_hash && is_position_output(is_pledge_lock, &raw_tx, *index as usize, is_udt)
+        }) {
+            return Some(false);
+        }
+        if &tx_hash == funding_tx_hash {
+            return Some(true);
+        }
+        out_points = raw_tx
+            .inputs()
+            .into_iter()
+            .map(|input| unpack_out_point(&input.previous_output()))
+            .collect();
+    }
+    Some(false)
+}
+
+fn is_position_output<F: Fn(&Script) -> bool>(
+    is_pledge_lock: &F,
+    raw_tx: &RawTransaction,
+    index: usize,
+    is_udt: bool,
+) -> bool {
+    match (
+        raw_tx.outputs().get(index),
+        raw_tx.outputs_data().get(index),
+    ) {
+        (Some(output), Some(data)) => {
+            is_pledge_lock(&output.lock())
+                && PledgeInfo::from_slice(&data.raw_data(), is_udt).pledged > 0
+        }
+        _ => false,
+    }
+}
+
+// The pledges of the positions of the funding transaction in the inputs and the cell deps, ordered by the backers.
+// The backer is the sender in the args of the pledge lock, and may have bought the positions of other backers.
+pub fn load_position_weights<F: Fn(&Script) -> bool>(
+    is_pledge_lock: F,
+    funding_tx_hash: &[u8; 32],
+    is_udt: bool,
+) -> Result<Vec<([u8; 20], u128)>, SysError> {
+    let dep_out_points = load_cell_dep_out_points()?;
+    let proofs = load_position_proofs()?;
+    let mut counted: Vec<OutPoint> = Vec::new();
+    let mut weights: Vec<([u8; 20], u128)> = Vec::new();
+    for source in [Source::Input, Source::CellDep].iter() {
+        for (index, lock) in QueryIter::new(load_cell_lock, *source).enumerate() {
+            if !is_pledge_lock(&lock) {
+                continue;
+            }
+            let out_point = if *source == Source::Input {
+                load_input_out_point_of(index)?
+            } else {
+                match dep_out_points.get(index) {
+                    Some(out_point) => *out_point,
+                    None => continue,
+                }
+            };
+            // a position is counted once, even in both the inputs and the cell deps
+            if counted.contains(&out_point)
+                || !is_funded_position(
+                    &is_pledge_lock,
+                    &out_point,
+                    funding_tx_hash,
+                    &proofs,
+                    is_udt,
+                )
+            {
+                continue;
+            }
+            counted.push(out_point);
+            let mut backer = [0u8; 20];
+            backer.copy_from_slice(&lock.args().raw_data()[52..72]);
+            let pledged = PledgeInfo::try_from(index, *source, is_udt)?.pledged;
+            match weights.binary_search_by(|(other, _)| other.cmp(&backer)) {
+                Ok(position) => {
+                    weights[position].1 = weights[position]
+                        .1
+                        .checked_add(pledged)
+                        .ok_or(SysError::Encoding)?
+                }
+                Err(position) => weights.insert(position, (backer, pledged)),
+            }
+        }
+    }
+    Ok(weights)
+}
+
+// The weight of the backer in the weights ordered by the backers
+pub fn weight_of(weights: &[([u8; 20], u128)], backer: &[u8; 20]) -> u128 {
+    weights
+        .binary_search_by(|(other, _)| other.cmp(backer))
+        .map_or(0, |position| weights[position].1)
+}
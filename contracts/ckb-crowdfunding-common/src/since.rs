@@ -0,0 +1,52 @@
+use alloc::vec::Vec;
+use ckb_std::{
+    ckb_constants::Source,
+    high_level::{load_input_since, QueryIter},
+};
+
+const SINCE_FLAGS_MASK: u64 = 0xFF00_0000_0000_0000;
+const SINCE_ABSOLUTE_EPOCH_FLAG: u64 = 0x2000_0000_0000_0000;
+const SINCE_RELATIVE_EPOCH_FLAG: u64 = 0xA000_0000_0000_0000;
+const SINCE_EPOCH_NUMBER_MASK: u64 = 0x0000_0000_00FF_FFFF;
+
+pub fn load_group_inputs_since() -> Vec<u64> {
+    QueryIter::new(load_input_since, Source::GroupInput).collect::<Vec<_>>()
+}
+
+// The earliest epoch of the group inputs if all of them are locked by an absolute epoch since,
+// the transaction can't be committed before it.
+pub fn group_inputs_since_epoch() -> Option<u64> {
+    load_group_inputs_since()
+        .into_iter()
+        .try_fold(u64::MAX, |epoch, since| {
+            if since & SINCE_FLAGS_MASK == SINCE_ABSOLUTE_EPOCH_FLAG {
+                Some(epoch.min(since & SINCE_EPOCH_NUMBER_MASK))
+            } else {
+                None
+            }
+        })
+}
+
+// The latest epoch of the inputs locked by an absolute epoch since, the transaction can't be committed before it
+pub fn inputs_since_epoch() -> Option<u64> {
+    QueryIter::new(load_input_since, Source::Input)
+        .filter(|since| since & SINCE_FLAGS_MASK == SINCE_ABSOLUTE_EPOCH_FLAG)
+        .map(|since| since & SINCE_EPOCH_NUMBER_MASK)
+        .max()
+}
+
+// Check whether all the group inputs are locked by an absolute epoch since, which is not earlier than the epoch
+pub fn check_group_inputs_since_epoch(epoch: u64) -> bool {
+    group_inputs_since_epoch().map_or(false, |since_epoch| since_epoch >= epoch)
+}
+
+// The epoch number of an epoch with its fraction, as in the block headers
+pub fn epoch_number(epoch: u64) -> u64 {
+    epoch & SINCE_EPOCH_NUMBER_MASK
+}
+
+// Check whether the since locks the input for at least the epochs after the cell was created
+pub fn is_relative_epoch_since_of(since: u64, epochs: u64) -> bool {
+    since & SINCE_FLAGS_MASK == SINCE_RELATIVE_EPOCH_FLAG
+        && since & SINCE_EPOCH_NUMBER_MASK >= epochs
+}
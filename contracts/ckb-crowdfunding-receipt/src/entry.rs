@@ -0,0 +1,183 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::result::Result;
+
+// Import heap related library from `alloc`
+// https://doc.rust-lang.org/alloc/index.html
+use alloc::vec::Vec;
+
+// Import CKB syscalls and structures
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, packed::Script, prelude::*},
+    debug,
+    high_level::{
+        load_cell_capacity, load_cell_data, load_cell_lock, load_cell_type_hash, load_script,
+        look_for_dep_with_data_hash, QueryIter,
+    },
+};
+
+use crate::error::Error;
+use ckb_crowdfunding_common::data::{udt_amount, CrowdfundingInfo, ReceiptInfo};
+
+// The receipts and the pledges of a backer in the transaction
+#[derive(Default)]
+struct Position {
+    backer: [u8; 20],
+    receipt_input: u128,
+    receipt_output: u128,
+    pledge_input: u128,
+    pledge_output: u128,
+}
+
+pub fn main() -> Result<(), Error> {
+    let script = load_script()?;
+    let args: Bytes = script.args().unpack();
+    debug!("script args is {:?}", args);
+
+    // args[0..32] is the data hash of the c-cell (project id),
+    // args[32..64] and args[64] are the code hash and the hash type of the crowdfunding lock,
+    // args[65..97] is the type script hash of the UDT for the UDT campaigns
+    if args.len() != 65 && args.len() != 97 {
+        return Err(Error::InvalidArgument);
+    }
+    let currency = args.get(65..97);
+
+    let mut positions: Vec<Position> = Vec::new();
+    for data in QueryIter::new(load_cell_data, Source::GroupInput) {
+        let receipt = ReceiptInfo::from_slice(&data).map_err(|_| Error::ReceiptInvalidData)?;
+        let position = find_position(&mut positions, &receipt.backer);
+        position.receipt_input = add_amount(position.receipt_input, receipt.amount)?;
+    }
+    for data in QueryIter::new(load_cell_data, Source::GroupOutput) {
+        let receipt = ReceiptInfo::from_slice(&data).map_err(|_| Error::ReceiptInvalidData)?;
+        let position = find_position(&mut positions, &receipt.backer);
+        position.receipt_output = add_amount(position.receipt_output, receipt.amount)?;
+    }
+    for source in [Source::Input, Source::Output].iter() {
+        for (index, lock) in QueryIter::new(load_cell_lock, *source).enumerate() {
+            let position = match pledge_backer(&lock, &args)
+                .and_then(|backer| positions.iter_mut().find(|p| p.backer == backer))
+            {
+                Some(position) => position,
+                None => continue,
+            };
+            let amount = load_pledge_amount(index, *source, currency)?;
+            if *source == Source::Input {
+                position.pledge_input = add_amount(position.pledge_input, amount)?;
+            } else {
+                position.pledge_output = add_amount(position.pledge_output, amount)?;
+            }
+        }
+    }
+
+    // The receipts go with the pledges when the backer transfers them to another backer,
+    // each backer minting or burning as much as its pledges gain or lose.
+    // The receipts always match the pledges: the backer mints exactly the amount pledged and
+    // burns exactly the amount refunded, though the positions are still read from the pledge
+    // cells, never from the receipts.
+    let receipts_input = positions
+        .iter()
+        .try_fold(0, |sum, p| add_amount(sum, p.receipt_input))?;
+    let receipts_output = positions
+        .iter()
+        .try_fold(0, |sum, p| add_amount(sum, p.receipt_output))?;
+    let transferred = receipts_input == receipts_output;
+
+    for position in positions {
+        if position.receipt_output > position.receipt_input {
+            // the receipts are minted with the capacity newly locked by the backer
+            let minted = position.receipt_output - position.receipt_input;
+            let pledged = position.pledge_output.checked_sub(position.pledge_input);
+            match pledged {
+                Some(pledged) if transferred && pledged == minted => (),
+                _ if transferred => return Err(Error::TransferAmountWrong),
+                Some(pledged) if pledged == minted => check_pledge_limits(&args[0..32], minted)?,
+                _ => return Err(Error::MintAmountWrong),
+            }
+        } else if position.receipt_output < position.receipt_input {
+            // the receipts are burned when the backer takes the pledge back
+            let burned = position.receipt_input - position.receipt_output;
+            match position.pledge_input.checked_sub(position.pledge_output) {
+                Some(refunded) if transferred && refunded != burned => {
+                    return Err(Error::TransferAmountWrong)
+                }
+                Some(refunded) if refunded == burned => (),
+                Some(refunded) if refunded > 0 => return Err(Error::BurnAmountWrong),
+                _ => return Err(Error::BurnWithoutRefund),
+            }
+        }
+    }
+    Ok(())
+}
+
+// The pledge is created with the receipt, within the limits of the project
+fn check_pledge_limits(project_id: &[u8], amount: u128) -> Result<(), Error> {
+    let index = look_for_dep_with_data_hash(project_id).map_err(|_| Error::NoCCell)?;
+    let crowdfunding_info =
+        CrowdfundingInfo::try_from(index).map_err(|_| Error::CCellInvalidData)?;
+    if amount > crowdfunding_info.hard_cap {
+        return Err(Error::HardCapExceeded);
+    }
+    if amount < crowdfunding_info.min_pledge {
+        return Err(Error::PledgeBelowMinimum);
+    }
+    if amount > crowdfunding_info.max_pledge {
+        return Err(Error::PledgeAboveMaximum);
+    }
+    Ok(())
+}
+
+fn find_position<'a>(positions: &'a mut Vec<Position>, backer: &[u8; 20]) -> &'a mut Position {
+    match positions.iter().position(|p| &p.backer == backer) {
+        Some(index) => &mut positions[index],
+        None => {
+            positions.push(Position {
+                backer: *backer,
+                ..Default::default()
+            });
+            positions.last_mut().unwrap()
+        }
+    }
+}
+
+fn add_amount(a1: u128, a2: u128) -> Result<u128, Error> {
+    a1.checked_add(a2).ok_or(Error::Encoding)
+}
+
+// The capacity of the pledge for the CKB campaigns, or the UDT amount for the UDT campaigns
+fn load_pledge_amount(
+    index: usize,
+    source: Source,
+    currency: Option<&[u8]>,
+) -> Result<u128, Error> {
+    match currency {
+        None => Ok(load_cell_capacity(index, source)? as u128),
+        Some(udt_type_hash) => {
+            if load_cell_type_hash(index, source)?
+                .as_ref()
+                .map(|hash| &hash[..])
+                != Some(udt_type_hash)
+            {
+                return Ok(0);
+            }
+            let data = load_cell_data(index, source)?;
+            udt_amount(&data).ok_or(Error::Encoding)
+        }
+    }
+}
+
+// The backer (args[52..72] of the pledge lock) if the cell is a pledge of the project
+fn pledge_backer(lock: &Script, args: &[u8]) -> Option<[u8; 20]> {
+    let lock_args = lock.args().raw_data();
+    if lock.code_hash().as_slice() != &args[32..64]
+        || lock.hash_type().as_slice() != &args[64..65]
+        || lock_args.len() < 104
+        || lock_args[0..32] != args[0..32]
+    {
+        return None;
+    }
+    let mut backer = [0u8; 20];
+    backer.copy_from_slice(&lock_args[52..72]);
+    Some(backer)
+}
@@ -0,0 +1,35 @@
+use ckb_std::error::SysError;
+
+/// Error
+#[repr(i8)]
+pub enum Error {
+    IndexOutOfBound = 1,
+    ItemMissing,
+    LengthNotEnough,
+    Encoding,
+    // Add customized errors here...
+    InvalidArgument = 5,
+    ReceiptInvalidData,
+    MintAmountWrong,
+    BurnWithoutRefund,
+    NoCCell,
+    CCellInvalidData = 10,
+    HardCapExceeded,
+    PledgeBelowMinimum,
+    PledgeAboveMaximum,
+    TransferAmountWrong,
+    BurnAmountWrong,
+}
+
+impl From<SysError> for Error {
+    fn from(err: SysError) -> Self {
+        use SysError::*;
+        match err {
+            IndexOutOfBound => Self::IndexOutOfBound,
+            ItemMissing => Self::ItemMissing,
+            LengthNotEnough(_) => Self::LengthNotEnough,
+            Encoding => Self::Encoding,
+            Unknown(err_code) => panic!("unexpected sys error {}", err_code),
+        }
+    }
+}
@@ -49,6 +49,57 @@ int load_and_hash_witness(blake2b_state *ctx, size_t index, size_t source) {
   return CKB_SUCCESS;
 }
 
+/*
+ * Recover the public key from the signature of the message,
+ * and output the blake160 hash of the compressed public key.
+ */
+static int recover_public_key_hash(const uint8_t *message,
+                                   const uint8_t *signature_bytes,
+                                   uint8_t *output_public_key_hash) {
+  unsigned char temp[PUBKEY_SIZE];
+  blake2b_state blake2b_ctx;
+  int ret;
+
+  secp256k1_context context;
+  uint8_t secp_data[CKB_SECP256K1_DATA_SIZE];
+  ret = ckb_secp256k1_custom_load_data(secp_data);
+  if (ret != 0) {
+    return ret;
+  }
+  ret = ckb_secp256k1_custom_verify_only_initialize(&context, secp_data);
+  if (ret != 0) {
+    return ret;
+  }
+
+  secp256k1_ecdsa_recoverable_signature signature;
+  if (secp256k1_ecdsa_recoverable_signature_parse_compact(
+          &context, &signature, signature_bytes,
+          signature_bytes[RECID_INDEX]) == 0) {
+    return ERROR_SECP_PARSE_SIGNATURE;
+  }
+
+  // Recover pubkey
+  secp256k1_pubkey pubkey;
+  if (secp256k1_ecdsa_recover(&context, &pubkey, &signature, message) != 1) {
+    return ERROR_SECP_RECOVER_PUBKEY;
+  }
+
+  // Check pubkey hash
+  size_t pubkey_size = PUBKEY_SIZE;
+  if (secp256k1_ec_pubkey_serialize(&context, temp, &pubkey_size, &pubkey,
+                                    SECP256K1_EC_COMPRESSED) != 1) {
+    return ERROR_SECP_SERIALIZE_PUBKEY;
+  }
+
+  blake2b_init(&blake2b_ctx, BLAKE2B_BLOCK_SIZE);
+  blake2b_update(&blake2b_ctx, temp, pubkey_size);
+  blake2b_final(&blake2b_ctx, temp, BLAKE2B_BLOCK_SIZE);
+
+  memcpy(output_public_key_hash, temp, BLAKE160_SIZE);
+
+  return CKB_SUCCESS;
+}
+
 int validate_secp256k1_blake2b_sighash_all(uint8_t *output_public_key_hash) {
   unsigned char temp[TEMP_SIZE];
   unsigned char lock_bytes[SIGNATURE_SIZE];
@@ -125,42 +176,15 @@ int validate_secp256k1_blake2b_sighash_all(uint8_t *output_public_key_hash) {
   }
   blake2b_final(&blake2b_ctx, message, BLAKE2B_BLOCK_SIZE);
 
-  // Load signature
-  secp256k1_context context;
-  uint8_t secp_data[CKB_SECP256K1_DATA_SIZE];
-  ret = ckb_secp256k1_custom_load_data(secp_data);
-  if (ret != 0) {
-    return ret;
-  }
-  ret = ckb_secp256k1_custom_verify_only_initialize(&context, secp_data);
-  if (ret != 0) {
-    return ret;
-  }
-
-  secp256k1_ecdsa_recoverable_signature signature;
-  if (secp256k1_ecdsa_recoverable_signature_parse_compact(
-          &context, &signature, lock_bytes, lock_bytes[RECID_INDEX]) == 0) {
-    return ERROR_SECP_PARSE_SIGNATURE;
-  }
-
-  // Recover pubkey
-  secp256k1_pubkey pubkey;
-  if (secp256k1_ecdsa_recover(&context, &pubkey, &signature, message) != 1) {
-    return ERROR_SECP_RECOVER_PUBKEY;
-  }
-
-  // Check pubkey hash
-  size_t pubkey_size = PUBKEY_SIZE;
-  if (secp256k1_ec_pubkey_serialize(&context, temp, &pubkey_size, &pubkey,
-                                    SECP256K1_EC_COMPRESSED) != 1) {
-    return ERROR_SECP_SERIALIZE_PUBKEY;
-  }
-
-  blake2b_init(&blake2b_ctx, BLAKE2B_BLOCK_SIZE);
-  blake2b_update(&blake2b_ctx, temp, pubkey_size);
-  blake2b_final(&blake2b_ctx, temp, BLAKE2B_BLOCK_SIZE);
-
-  memcpy(output_public_key_hash, temp, BLAKE160_SIZE);
+  return recover_public_key_hash(message, lock_bytes, output_public_key_hash);
+}
 
-  return CKB_SUCCESS;
+/*
+ * Recover the signer of a 32 bytes message signed out of the transaction,
+ * e.g. an attestation, and output the blake160 hash of its public key.
+ */
+int recover_secp256k1_blake2b_message(const uint8_t *message,
+                                      const uint8_t *signature,
+                                      uint8_t *output_public_key_hash) {
+  return recover_public_key_hash(message, signature, output_public_key_hash);
 }
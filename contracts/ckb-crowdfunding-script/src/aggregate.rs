@@ -0,0 +1,143 @@
+use core::result::Result;
+
+use alloc::vec::Vec;
+
+use super::{helper, tally};
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{CrowdfundingInfo, TallyInfo, TallyLeaf, TALLY_LEAF_LEN},
+    merkle, position,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    high_level::{load_cell_data, load_script, QueryIter},
+};
+
+// kinds of the challenges
+const CHALLENGE_WRONG_SIGNATURE: u8 = 0;
+const CHALLENGE_VOTE_LEFT_OUT: u8 = 1;
+const CHALLENGE_WEIGHT_TOO_HIGH: u8 = 2;
+
+// The tally cell of a milestone carries the root of the Merkle-sum tree of the votes signed by the backers,
+// whose leaves and weights are checked by the vote type script when it is submitted.
+// The signatures are too many to verify in a transaction, and the votes left out cannot be seen from the tree,
+// so anyone can take the tally cell before it is confirmed with a challenge in the args:
+// the kind 1 byte, then for a leaf whose signature is not of its backer,
+// the leaf 122 bytes, the number of the siblings 1 byte and the siblings,
+// or for a vote signed by a backer with a position but left out of the tree,
+// the vote 122 bytes and the proof of the leaf where it should have been, in the same layout,
+// or for a leaf weighing more than all the positions but those of the backers of the other leaves,
+// the proof of the leaf and then the proofs of the other leaves ordered by their backers, in the same layout.
+// A leaf weighs no more than the position of its backer and the positions of the backers delegating to it,
+// which have no leaves of their own, so the other leaves prove the weight too high
+// without the delegations the challenger could leave out.
+pub fn validate_challenge(
+    crowdfunding_info: &CrowdfundingInfo,
+    state_type_hash: &[u8; 32],
+    milestone: u8,
+    challenge: &[u8],
+) -> Result<(), Error> {
+    if QueryIter::new(load_cell_data, Source::GroupInput).count() != 1 {
+        return Err(Error::TallyInvalidData);
+    }
+    let tally_info =
+        TallyInfo::try_from(0, Source::GroupInput).map_err(|_| Error::TallyInvalidData)?;
+    if tally_info.confirmed {
+        return Err(Error::InvalidAction);
+    }
+
+    let script = load_script()?;
+    let project_id = &script.args().raw_data()[0..32];
+    match challenge.first() {
+        Some(&CHALLENGE_WRONG_SIGNATURE) => {
+            let (leaf, _) = prove_leaf(&tally_info, &challenge[1..])?;
+            if is_signed_by_backer(&leaf, project_id, milestone) {
+                return Err(Error::TallyNotFraudulent);
+            }
+            Ok(())
+        }
+        Some(&CHALLENGE_VOTE_LEFT_OUT) => {
+            let vote = challenge
+                .get(1..1 + TALLY_LEAF_LEN)
+                .and_then(|vote| TallyLeaf::from_slice(vote).ok())
+                .ok_or(Error::TallyProofWrong)?;
+            let (leaf, proof) = prove_leaf(&tally_info, &challenge[1 + TALLY_LEAF_LEN..])?;
+            // the leaf is the last one before the vote, or the first one after it
+            let left_out = if leaf.backer < vote.backer {
+                leaf.next == [0u8; 20] || vote.backer < leaf.next
+            } else {
+                vote.backer < leaf.backer && merkle::is_leftmost(proof)
+            };
+            if !left_out || !is_signed_by_backer(&vote, project_id, milestone) {
+                return Err(Error::TallyNotFraudulent);
+            }
+            let weights = tally::load_position_weights(crowdfunding_info, &script)?;
+            if position::weight_of(&weights, &vote.backer) == 0 {
+                return Err(Error::TallyNotFraudulent);
+            }
+            Ok(())
+        }
+        Some(&CHALLENGE_WEIGHT_TOO_HIGH) => {
+            let leaves = prove_leaves(&tally_info, &challenge[1..])?;
+            let (leaf, others) = leaves.split_first().ok_or(Error::TallyProofWrong)?;
+            if others
+                .windows(2)
+                .any(|pair| pair[0].backer >= pair[1].backer)
+                || others.iter().any(|other| other.backer == leaf.backer)
+            {
+                return Err(Error::TallyProofWrong);
+            }
+            let state_info = helper::load_current_state_info(state_type_hash)?;
+            let weights = tally::load_position_weights(crowdfunding_info, &script)?;
+            let mut weight = crowdfunding_info.total_vote_weight(&state_info);
+            for other in others {
+                weight = weight.saturating_sub(
+                    crowdfunding_info.vote_weight(position::weight_of(&weights, &other.backer)),
+                );
+            }
+            if leaf.weight <= weight {
+                return Err(Error::TallyNotFraudulent);
+            }
+            Ok(())
+        }
+        _ => Err(Error::TallyProofWrong),
+    }
+}
+
+// Return the leaves proven one after another to be in the tree of the tally
+fn prove_leaves(tally_info: &TallyInfo, mut proven: &[u8]) -> Result<Vec<TallyLeaf>, Error> {
+    let mut leaves = Vec::new();
+    while !proven.is_empty() {
+        let siblings = *proven.get(TALLY_LEAF_LEN).ok_or(Error::TallyProofWrong)? as usize;
+        let len = TALLY_LEAF_LEN + 1 + siblings * merkle::SUM_SIBLING_LEN;
+        let leaf_proof = proven.get(0..len).ok_or(Error::TallyProofWrong)?;
+        leaves.push(prove_leaf(tally_info, leaf_proof)?.0);
+        proven = &proven[len..];
+    }
+    Ok(leaves)
+}
+
+// Return the leaf proven to be in the tree of the tally and its proof
+fn prove_leaf<'a>(
+    tally_info: &TallyInfo,
+    proven: &'a [u8],
+) -> Result<(TallyLeaf, &'a [u8]), Error> {
+    let siblings = *proven.get(TALLY_LEAF_LEN).ok_or(Error::TallyProofWrong)? as usize;
+    let proof = proven
+        .get(TALLY_LEAF_LEN + 1..)
+        .filter(|proof| proof.len() == siblings * merkle::SUM_SIBLING_LEN)
+        .ok_or(Error::TallyProofWrong)?;
+    let leaf =
+        TallyLeaf::from_slice(&proven[0..TALLY_LEAF_LEN]).map_err(|_| Error::TallyProofWrong)?;
+    if merkle::sum_proof_root(leaf.node(), proof) != Some(tally_info.root()) {
+        return Err(Error::TallyProofWrong);
+    }
+    Ok((leaf, proof))
+}
+
+fn is_signed_by_backer(leaf: &TallyLeaf, project_id: &[u8], milestone: u8) -> bool {
+    helper::recover_blake2b_message_signer(&leaf.message(project_id, milestone), &leaf.signature)
+        .map_or(false, |signer| {
+            helper::secp256k1_lock_hash(&signer) == leaf.backer
+        })
+}
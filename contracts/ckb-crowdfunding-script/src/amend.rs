@@ -0,0 +1,45 @@
+use core::result::Result;
+
+use super::{helper, tally};
+use crate::error::Error;
+use ckb_crowdfunding_common::data::{as_u64_be, CrowdfundingInfo};
+use ckb_std::{
+    ckb_constants::Source,
+    high_level::{load_cell_data, load_script, load_script_hash, QueryIter},
+};
+
+// The creator proposes the new time of the next milestone in the data of the proposal cell, 8 bytes,
+// which is locked by the crowdfunding lock with the args of the escrow cell of the milestone and one more byte of 1.
+// The proposal is consumed when the output state cell records the amendment with its lock hash,
+// and the votes on the amendment must reach the approval ratio and the quorum of the milestone.
+pub fn validate(
+    crowdfunding_info: &CrowdfundingInfo,
+    state_type_hash: &[u8; 32],
+    milestone: u8,
+) -> Result<(), Error> {
+    if QueryIter::new(load_cell_data, Source::GroupInput).count() != 1 {
+        return Err(Error::AmendmentWrong);
+    }
+    let data = load_cell_data(0, Source::GroupInput)?;
+    if data.len() != 8 {
+        return Err(Error::AmendmentWrong);
+    }
+    let time = as_u64_be(&data);
+    let state_info =
+        helper::load_state_info(state_type_hash, Source::Output)?.ok_or(Error::AmendmentWrong)?;
+    let script_hash = load_script_hash()?;
+    match state_info.amendments.last() {
+        Some(amendment)
+            if amendment.milestone == milestone
+                && amendment.time == time
+                && amendment.proposal == script_hash => {}
+        _ => return Err(Error::AmendmentWrong),
+    }
+    tally::check_amendment_approved(
+        crowdfunding_info,
+        &load_script()?,
+        &state_info,
+        milestone,
+        time,
+    )
+}
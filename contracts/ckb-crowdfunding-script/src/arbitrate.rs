@@ -0,0 +1,106 @@
+use core::result::Result;
+
+use alloc::vec::Vec;
+
+use super::{claim, escrow, helper, veto};
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{CrowdfundingInfo, Phase, ARBITRATION_EPOCHS},
+    since,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    high_level::{load_cell_data, load_script_hash, QueryIter},
+};
+
+// the decisions of the arbitrator
+const DECISION_RELEASE: u8 = 0;
+const DECISION_REFUND: u8 = 1;
+
+// When the milestone is not released the arbitration epochs after its time and the backers have not approved it,
+// or have vetoed it in the veto mode, the vote has failed, and the arbitrator unlocking a cell of its lock in the inputs decides on the escrow cell of the milestone
+// with the decision 1 byte in the args of the dispute.
+// The release pays the creator as the release by the creator does,
+// and the refund turns the escrow cell into the refund record of the same capacity,
+// which refunds the backers as they take their positions back.
+pub fn validate_dispute(
+    crowdfunding_info: &CrowdfundingInfo,
+    receiver_lock_hash: &[u8; 20],
+    state_type_hash: &[u8; 32],
+    milestone: u8,
+    decision: &[u8],
+) -> Result<(), Error> {
+    let arbitrator = crowdfunding_info.arbitrator.ok_or(Error::InvalidAction)?;
+    if !helper::has_input_by_lock_hash(&arbitrator) {
+        return Err(Error::ArbitratorNotSigned);
+    }
+    if QueryIter::new(load_cell_data, Source::GroupInput).count() != 1 {
+        return Err(Error::EscrowInvalidData);
+    }
+    let state_info = helper::load_current_state_info(state_type_hash)?;
+    if state_info.phase == Phase::Failed || state_info.phase == Phase::Cancelled {
+        return Err(Error::InvalidAction);
+    }
+    let state_released = state_info
+        .released(crowdfunding_info.releases())
+        .ok_or(Error::StateInvalidData)?;
+    let dispute_epoch = milestone
+        .checked_add(1)
+        .and_then(|release| crowdfunding_info.release_epoch(release))
+        .and_then(|epoch| epoch.checked_add(ARBITRATION_EPOCHS))
+        .ok_or(Error::Encoding)?;
+    if state_released < milestone.checked_add(2).ok_or(Error::Encoding)?
+        || !since::check_group_inputs_since_epoch(dispute_epoch)
+    {
+        return Err(Error::ReleaseTooEarly);
+    }
+    // the milestone approved is released by the creator, never disputed
+    if escrow::check_milestone_approved(crowdfunding_info, &state_info, milestone).is_ok() {
+        return Err(Error::MilestoneApproved);
+    }
+
+    let currency = &crowdfunding_info.currency;
+    let escrow_amount = helper::load_cell_amount(0, Source::GroupInput, currency)?;
+    match decision {
+        [DECISION_RELEASE] => {
+            let fee = match &crowdfunding_info.platform_fee {
+                Some(platform_fee) => platform_fee.fee_of(escrow_amount).ok_or(Error::Encoding)?,
+                None => 0,
+            };
+            if crowdfunding_info.platform_fee.is_some() || !crowdfunding_info.payees.is_empty() {
+                claim::validate_payouts(crowdfunding_info, escrow_amount)?;
+            }
+            if crowdfunding_info.payees.is_empty()
+                && helper::sum_cells_amount_of_lock_hash(
+                    receiver_lock_hash,
+                    Source::Output,
+                    currency,
+                )? < escrow_amount - fee
+            {
+                return Err(Error::EscrowOutputsWrong);
+            }
+            Ok(())
+        }
+        [DECISION_REFUND] => {
+            let mut escrow_data = Vec::with_capacity(escrow::REFUND_RECORD_LEN);
+            escrow_data.extend_from_slice(
+                &veto::Objections::load(0, Source::GroupInput)?.finalize_tx_hash,
+            );
+            escrow_data.extend_from_slice(&state_info.total_pledged.to_be_bytes());
+            let script_hash = load_script_hash()?;
+            let mut escrow_lock_hash = [0u8; 20];
+            escrow_lock_hash.copy_from_slice(&script_hash[0..20]);
+            let index = match helper::position_outputs_by_lock_hash(&escrow_lock_hash)[..] {
+                [index] => index,
+                _ => return Err(Error::EscrowOutputsWrong),
+            };
+            if helper::load_cell_amount(index, Source::Output, currency)? < escrow_amount
+                || load_cell_data(index, Source::Output)? != escrow_data
+            {
+                return Err(Error::EscrowOutputsWrong);
+            }
+            Ok(())
+        }
+        _ => Err(Error::InvalidAction),
+    }
+}
@@ -1,54 +1,259 @@
 use core::result::Result;
 
 use super::helper;
+use super::tally;
 use crate::error::Error;
-use ckb_std::{ckb_constants::Source, high_level::load_script_hash};
+use ckb_crowdfunding_common::{
+    data::{CrowdfundingInfo, Payee, Phase, PledgeInfo, StateInfo},
+    merkle, since,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::packed::Script,
+    high_level::{load_script, load_script_hash},
+};
 
+// The creator takes the releases recorded by the state cell from the pledges one by one
+// (the startup amount first, then the milestones), and every backer pays the share in proportion to the pledge.
+// The pledges of the vesting projects pay whatever the state cell has released since they were settled.
+// The args of the claim is the Merkle proof of the backer when the project has an allow-list.
 pub fn validate(
-    sender_lock_hash: &[u8; 20],
-    receiver_lock_hash: &[u8; 20],
-    cheque_witness_is_none: bool,
+    crowdfunding_info: &CrowdfundingInfo,
+    state_type_hash: &[u8; 32],
+    allow_list_proof: &[u8],
 ) -> Result<(), Error> {
+    let currency = &crowdfunding_info.currency;
+    let pledge_info = helper::load_group_pledge_info(currency.is_some())?;
+    let released = pledge_info.released;
+    let state_info = helper::load_current_state_info(state_type_hash)?;
+    let state_released = match state_info.phase {
+        Phase::Cancelled => return Err(Error::ProjectCancelled),
+        Phase::Failed => return Err(Error::ProjectFailed),
+        _ => state_info
+            .released(crowdfunding_info.releases())
+            .ok_or(Error::StateInvalidData)?,
+    };
+    let vesting = crowdfunding_info.vesting && released > 0;
+    if (vesting && pledge_info.settled >= state_info.total_released)
+        || (!vesting && released >= state_released)
+    {
+        return Err(Error::NothingToRelease);
+    }
+
+    let script = load_script()?;
+    if released == 0 {
+        validate_funding(
+            crowdfunding_info,
+            state_type_hash,
+            &script,
+            &state_info,
+            allow_list_proof,
+        )?;
+        // the positions weighing the votes are created only by the finalize transaction
+        if helper::sum_outputs_positions_pledged(&script, currency.is_some())? != 0 {
+            return Err(Error::ReleaseCapacityWrong);
+        }
+    }
+
+    let (release_amount, pool_amount) = if vesting {
+        (
+            state_info.total_released - pledge_info.settled,
+            state_info
+                .total_pledged
+                .checked_sub(pledge_info.settled)
+                .ok_or(Error::PoolCapacityNotEnough)?,
+        )
+    } else {
+        let (_, release_amount) = crowdfunding_info
+            .release(released, state_info.total_pledged)
+            .ok_or(Error::NothingToRelease)?;
+        let pool_amount = crowdfunding_info
+            .released_amount(released, state_info.total_pledged)
+            .and_then(|released_amount| state_info.total_pledged.checked_sub(released_amount))
+            .ok_or(Error::PoolCapacityNotEnough)?;
+        (release_amount, pool_amount)
+    };
+    if release_amount > pool_amount {
+        return Err(Error::PoolCapacityNotEnough);
+    }
+    // the milestones the backer pays for by the claim
+    let released_from = state_info.total_pledged - pool_amount;
+    tally::check_milestones_approved(
+        crowdfunding_info,
+        &script,
+        &state_info,
+        released_from,
+        released_from + release_amount,
+    )?;
+
     let script_hash = load_script_hash()?;
-    let mut cheque_lock_hash = [0u8; 20];
-    cheque_lock_hash.copy_from_slice(&script_hash[0..20]);
+    let mut crowdfunding_lock_hash = [0u8; 20];
+    crowdfunding_lock_hash.copy_from_slice(&script_hash[0..20]);
 
-    if check_cheque_inputs_since_not_zero() {
-        return Err(Error::ClaimChequeInputSinceNotZero);
+    let group_amount = helper::sum_group_inputs_amount(currency)?;
+    let share = pro_rata_share(group_amount, release_amount, pool_amount).ok_or(Error::Encoding)?;
+    let sum_outputs_amount =
+        helper::sum_cells_amount_of_lock_hash(&crowdfunding_lock_hash, Source::Output, currency)?;
+    if sum_outputs_amount < group_amount.checked_sub(share).ok_or(Error::Encoding)? {
+        return Err(Error::ReleaseCapacityWrong);
     }
 
-    if !check_sender_cells_capacity_same(sender_lock_hash, &cheque_lock_hash)? {
-        return Err(Error::SenderCapacityNotSame);
+    // The operator and the payees are paid out of the release of all the pledges claimed in the transaction,
+    // so they are paid once however many backers are claimed together.
+    if crowdfunding_info.platform_fee.is_some() || !crowdfunding_info.payees.is_empty() {
+        let project_amount = helper::sum_project_inputs_amount(&script, currency)?;
+        let project_share =
+            pro_rata_share(project_amount, release_amount, pool_amount).ok_or(Error::Encoding)?;
+        validate_payouts(crowdfunding_info, project_share)?;
     }
 
-    if cheque_witness_is_none {
-        match helper::position_input_by_lock_hash(receiver_lock_hash) {
-            Some(position) => helper::check_witness_args(position),
-            None => Err(Error::NoMatchedInputs),
+    check_pledge_capacity_conserved(&crowdfunding_lock_hash, currency)?;
+
+    let released_pledge_info = if crowdfunding_info.vesting {
+        PledgeInfo {
+            released: state_released,
+            settled: state_info.total_released,
+            pledged: 0,
         }
     } else {
-        Ok(())
+        PledgeInfo {
+            released: released + 1,
+            settled: 0,
+            pledged: 0,
+        }
+    };
+    if !helper::check_outputs_pledge_info(
+        &crowdfunding_lock_hash,
+        &released_pledge_info,
+        currency.is_some(),
+    )? {
+        return Err(Error::ReleaseCapacityWrong);
     }
+    Ok(())
 }
 
-fn check_sender_cells_capacity_same(
-    sender_lock_hash: &[u8; 20],
-    cheque_lock_hash: &[u8; 20],
-) -> Result<bool, Error> {
-    let sum_sender_inputs_capacity =
-        helper::sum_cells_capacity_of_lock_hash(sender_lock_hash, Source::Input)?;
-    let sum_sender_outputs_capacity =
-        helper::sum_cells_capacity_of_lock_hash(sender_lock_hash, Source::Output)?;
-    let sum_cheque_inputs_capacity =
-        helper::sum_cells_capacity_of_lock_hash(cheque_lock_hash, Source::Input)?;
-
-    match sum_sender_inputs_capacity.checked_add(sum_cheque_inputs_capacity) {
-        Some(sum_inputs_capacity) => Ok(sum_inputs_capacity == sum_sender_outputs_capacity),
-        None => Err(Error::Encoding),
+// The pledges are counted into the state cell only when the project gets funded,
+// and only the counted pledges pay for the releases.
+pub fn validate_funding(
+    crowdfunding_info: &CrowdfundingInfo,
+    state_type_hash: &[u8; 32],
+    script: &Script,
+    state_info: &StateInfo,
+    allow_list_proof: &[u8],
+) -> Result<(), Error> {
+    let input_state_info = helper::load_state_info(state_type_hash, Source::Input)?;
+    if input_state_info.map(|info| info.phase) != Some(Phase::Pledging) {
+        return Err(Error::PledgeNotCounted);
+    }
+    if helper::sum_project_inputs_amount(script, &crowdfunding_info.currency)?
+        != state_info.total_pledged
+    {
+        return Err(Error::TotalPledgedWrong);
     }
+    if state_info.total_pledged > crowdfunding_info.hard_cap {
+        return Err(Error::HardCapExceeded);
+    }
+    // the quadratic voting records the weight of all the backers, which the approvals are of
+    let total_weight = if crowdfunding_info.quadratic {
+        helper::sum_inputs_quadratic_weight(script, &crowdfunding_info.currency)?
+    } else {
+        0
+    };
+    if state_info.total_weight != total_weight {
+        return Err(Error::TotalWeightWrong);
+    }
+    helper::check_inputs_pledge_limits(script, crowdfunding_info)?;
+    if let Some(allow_list) = &crowdfunding_info.allow_list {
+        let leaf = merkle::leaf_hash(&helper::pledge_sender_lock_hash(script));
+        if !merkle::verify_proof(allow_list, leaf, allow_list_proof) {
+            return Err(Error::BackerNotAllowed);
+        }
+    }
+    // the funding transaction is committed no earlier than the since of its inputs, nor than the pledge time
+    if let Some(kyc_issuer) = &crowdfunding_info.kyc_issuer {
+        let funding_epoch = since::inputs_since_epoch()
+            .unwrap_or_default()
+            .max(crowdfunding_info.pledge_time);
+        helper::check_backer_attestation(
+            kyc_issuer,
+            &script.args().raw_data()[0..32],
+            &helper::pledge_sender_lock_hash(script),
+            funding_epoch,
+        )?;
+    }
+    Ok(())
+}
+
+// The operator takes the platform fee out of the amount released, and the payees split the rest
+pub fn validate_payouts(crowdfunding_info: &CrowdfundingInfo, amount: u128) -> Result<(), Error> {
+    let currency = &crowdfunding_info.currency;
+    let fee = match &crowdfunding_info.platform_fee {
+        Some(platform_fee) => {
+            let fee = platform_fee.fee_of(amount).ok_or(Error::Encoding)?;
+            if helper::sum_cells_amount_of_lock_hash(
+                &platform_fee.operator,
+                Source::Output,
+                currency,
+            )? != fee
+            {
+                return Err(Error::PlatformFeeWrong);
+            }
+            fee
+        }
+        None => 0,
+    };
+    validate_payees(
+        &crowdfunding_info.payees,
+        amount.checked_sub(fee).ok_or(Error::Encoding)?,
+        currency,
+    )
 }
 
-fn check_cheque_inputs_since_not_zero() -> bool {
-    let cheque_inputs_since = helper::load_group_inputs_since();
-    cheque_inputs_since.into_iter().any(|since| since != 0)
+// the creator takes only the UDT, the capacity carrying it stays in the pledge cells
+pub fn check_pledge_capacity_conserved(
+    crowdfunding_lock_hash: &[u8; 20],
+    currency: &Option<[u8; 32]>,
+) -> Result<(), Error> {
+    if currency.is_some()
+        && helper::sum_cells_amount_of_lock_hash(crowdfunding_lock_hash, Source::Output, &None)?
+            < helper::sum_group_inputs_amount(&None)?
+    {
+        return Err(Error::PledgeCapacityNotConserved);
+    }
+    Ok(())
+}
+
+// Every payee gets at least floor(amount * weight / the sum of the weights),
+// the rounding dust is left to the creator.
+fn validate_payees(
+    payees: &[Payee],
+    amount: u128,
+    currency: &Option<[u8; 32]>,
+) -> Result<(), Error> {
+    let total_weight: u128 = payees.iter().map(|payee| payee.weight as u128).sum();
+    for payee in payees {
+        let payee_amount = amount
+            .checked_mul(payee.weight as u128)
+            .map(|amount| amount / total_weight)
+            .ok_or(Error::Encoding)?;
+        if helper::sum_cells_amount_of_lock_hash(&payee.lock_hash, Source::Output, currency)?
+            < payee_amount
+        {
+            return Err(Error::PayeeAmountWrong);
+        }
+    }
+    Ok(())
+}
+
+// share = floor(group_amount * release_amount / pool_amount)
+// Every share is rounded down, so the creator never gets more than the release,
+// and the rounding dust stays in the pledge cells to be refunded to the backers.
+// The group amount is divided before the multiplication, so the large UDT amounts never overflow the product.
+pub fn pro_rata_share(group_amount: u128, release_amount: u128, pool_amount: u128) -> Option<u128> {
+    if pool_amount == 0 {
+        return Some(0);
+    }
+    let whole = (group_amount / pool_amount).checked_mul(release_amount)?;
+    let rest = (group_amount % pool_amount).checked_mul(release_amount)? / pool_amount;
+    whole.checked_add(rest)
 }
@@ -0,0 +1,48 @@
+use core::result::Result;
+
+use alloc::vec::Vec;
+
+use super::helper;
+use crate::error::Error;
+use ckb_crowdfunding_common::data::Council;
+use ckb_std::{ckb_constants::Source, ckb_types::packed::Script, high_level::load_witness_args};
+
+const COUNCIL_SIGNATURE_LEN: usize = 66;
+
+// In the council mode, a milestone is approved by the signatures of the threshold of the council members
+// on the project id and the milestone index.
+// They are in the output_type of the witness of the first group input, [milestone 1 byte, signature 65 bytes] each.
+pub fn check_council_approved(
+    council: &Council,
+    script: &Script,
+    milestone: u8,
+) -> Result<(), Error> {
+    let signatures = load_witness_args(0, Source::GroupInput)
+        .ok()
+        .and_then(|witness_args| witness_args.output_type().to_opt())
+        .map(|output_type| output_type.raw_data())
+        .unwrap_or_default();
+    if signatures.len() % COUNCIL_SIGNATURE_LEN != 0 {
+        return Err(Error::CouncilSignaturesWrong);
+    }
+
+    let message = Council::message(&script.args().raw_data()[0..32], milestone);
+    let mut signers: Vec<[u8; 20]> = Vec::new();
+    for entry in signatures.chunks_exact(COUNCIL_SIGNATURE_LEN) {
+        if entry[0] != milestone {
+            continue;
+        }
+        let mut signature = [0u8; 65];
+        signature.copy_from_slice(&entry[1..66]);
+        let signer = helper::recover_blake2b_message_signer(&message, &signature)
+            .map_err(|_| Error::CouncilSignaturesWrong)?;
+        if !council.members.contains(&signer) || signers.contains(&signer) {
+            return Err(Error::CouncilSignaturesWrong);
+        }
+        signers.push(signer);
+    }
+    if signers.len() < council.threshold as usize {
+        return Err(Error::MilestoneNotApprovedByCouncil);
+    }
+    Ok(())
+}
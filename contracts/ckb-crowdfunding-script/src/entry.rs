@@ -17,11 +17,26 @@ use ckb_std::{
     syscalls::{load_cell_by_field, load_cell_data},
 };
 
+use super::aggregate;
+use super::amend;
+use super::arbitrate;
 use super::claim;
-use super::data::CrowdfundingInfo;
+use super::escrow;
+use super::finalize;
 use super::helper;
+use super::transfer;
+use super::veto;
 use super::withdraw;
 use crate::error::Error;
+use ckb_crowdfunding_common::data::CrowdfundingInfo;
+
+// actions in the first byte of the input_type of the first group witness
+const ACTION_DEFAULT: u8 = 0; // claim for the creator and withdraw for the backer
+const ACTION_TRANSFER: u8 = 1; // the backer transfers the pledges to a new backer
+const ACTION_FINALIZE: u8 = 2; // the creator consolidates the pledges into the escrow cells of the milestones
+const ACTION_CHALLENGE: u8 = 3; // anyone proves a wrong vote in the tally cell of a milestone
+const ACTION_OBJECT: u8 = 4; // anyone counts the objections into the escrow cell of a milestone
+const ACTION_DISPUTE: u8 = 5; // the arbitrator decides on the escrow cell of a milestone not approved
 
 pub fn main() -> Result<(), Error> {
     // remove below examples and write your code here
@@ -31,7 +46,7 @@ pub fn main() -> Result<(), Error> {
     debug!("script args is {:?}", args);
 
     // return an error if args is invalid
-    if args.is_empty() {
+    if args.len() < 104 {
         return Err(Error::InvalidArgument);
     }
 
@@ -42,7 +57,7 @@ pub fn main() -> Result<(), Error> {
     debug!("index is {:?}", index);
 
     // read project data
-    let crowdfunding_info =
+    let mut crowdfunding_info =
         CrowdfundingInfo::try_from(index).map_err(|_| Error::CCellInvalidData)?;
     debug!("crowdfunding_info is {:?}", crowdfunding_info);
 
@@ -52,19 +67,80 @@ pub fn main() -> Result<(), Error> {
     receiver_lock_hash.copy_from_slice(&args[32..52]);
     debug!("receiver_lock_hash: {:?}", receiver_lock_hash);
     sender_lock_hash.copy_from_slice(&args[52..72]);
+    // the state cell of the project
+    let mut state_type_hash = [0u8; 32];
+    state_type_hash.copy_from_slice(&args[72..104]);
+    // the milestone times amended by the backers
+    helper::apply_amendments(&mut crowdfunding_info, &script, &state_type_hash)?;
+
+    // the escrow cell of the milestone in args[104]
+    if args.len() == helper::ESCROW_ARGS_LEN {
+        return match helper::load_action() {
+            (ACTION_OBJECT, _) => {
+                veto::validate_objection(&crowdfunding_info, &state_type_hash, args[104])
+            }
+            (ACTION_DISPUTE, decision) => arbitrate::validate_dispute(
+                &crowdfunding_info,
+                &receiver_lock_hash,
+                &state_type_hash,
+                args[104],
+                &decision,
+            ),
+            _ => escrow::validate(
+                &crowdfunding_info,
+                &receiver_lock_hash,
+                &state_type_hash,
+                args[104],
+            ),
+        };
+    }
+
+    // the proposal cell of the amendment of the milestone in args[104]
+    if args.len() == helper::TALLY_ARGS_LEN && args[105] == helper::AMENDMENT_KIND {
+        return amend::validate(&crowdfunding_info, &state_type_hash, args[104]);
+    }
+
+    // the tally cell of the milestone in args[104]
+    if args.len() == helper::TALLY_ARGS_LEN {
+        return match helper::load_action() {
+            (ACTION_CHALLENGE, challenge) => aggregate::validate_challenge(
+                &crowdfunding_info,
+                &state_type_hash,
+                args[104],
+                &challenge,
+            ),
+            (ACTION_DEFAULT, _) => {
+                if helper::validate_signature_of_receiver_and_sender(
+                    &receiver_lock_hash,
+                    &[0u8; 20],
+                )? {
+                    Ok(())
+                } else {
+                    Err(Error::WrongPubKey)
+                }
+            }
+            _ => Err(Error::InvalidAction),
+        };
+    }
 
     // unlock
     match helper::validate_signature_of_receiver_and_sender(&receiver_lock_hash, &sender_lock_hash)
     {
-        Ok(is_receiver) => {
-            if is_receiver {
-                // claim::validate(&sender_lock_hash, &receiver_lock_hash, false)
-                Ok(())
-            } else {
-                // withdraw::validate(&sender_lock_hash, false)
-                Ok(())
+        Ok(is_receiver) => match (is_receiver, helper::load_action()) {
+            (true, (ACTION_DEFAULT, action_args)) => {
+                claim::validate(&crowdfunding_info, &state_type_hash, &action_args)
+            }
+            (true, (ACTION_FINALIZE, action_args)) => {
+                finalize::validate(&crowdfunding_info, &state_type_hash, &action_args)
+            }
+            (false, (ACTION_DEFAULT, _)) => {
+                withdraw::validate(&crowdfunding_info, &state_type_hash)
+            }
+            (false, (ACTION_TRANSFER, _)) => {
+                transfer::validate(&crowdfunding_info, &sender_lock_hash, &state_type_hash)
             }
-        }
+            _ => Err(Error::InvalidAction),
+        },
         Err(_) => Err(Error::NoMatchedSignature),
     }
 }
@@ -10,15 +10,58 @@ pub enum Error {
     // Add customized errors here...
     InvalidArgument = 5,
     NoMatchedInputs,
-    WitnessSignatureWrong,
-    SenderCapacityNotSame,
-    WrongPubKey,
-    ClaimChequeInputSinceNotZero = 10,
-    WithdrawChequeInputSinceError,
-    Secp256k1,
+    WrongPubKey = 9,
+    Secp256k1 = 12,
     NoMatchedSignature,
     NoCCell,
     CCellInvalidData,
+    PledgeReleasedNotSame,
+    NothingToRelease,
+    ReleaseTooEarly,
+    PoolCapacityNotEnough = 20,
+    ReleaseCapacityWrong,
+    ProjectNotFailed,
+    ProjectCancelled,
+    ProjectFailed,
+    InvalidAction = 25,
+    NoStateCell,
+    StateInvalidData,
+    PledgeNotCounted,
+    TotalPledgedWrong,
+    ReleaseNotCollected = 30,
+    TransferOutputsWrong,
+    TransferCapacityWrong,
+    PledgeCapacityNotConserved,
+    HardCapExceeded,
+    PledgeBelowMinimum,
+    PledgeAboveMaximum,
+    BackerNotAllowed,
+    BackerNotAttested,
+    AttestationExpired,
+    PlatformFeeWrong,
+    PayeeAmountWrong,
+    EscrowOutputsWrong,
+    EscrowInvalidData,
+    NothingToRefund,
+    VoteInvalidData = 45,
+    MilestoneNotApproved,
+    TallyInvalidData,
+    TallyProofWrong,
+    TallyNotFraudulent,
+    MilestoneQuorumNotReached = 50,
+    MilestoneVetoed,
+    MilestoneNotEscrowed,
+    ObjectionsWrong,
+    CouncilSignaturesWrong,
+    MilestoneNotApprovedByCouncil = 55,
+    ArbitratorNotSigned,
+    TotalWeightWrong,
+    AmendmentWrong,
+    MilestoneApproved,
+    CommitmentDuplicated,
+    DelegationDuplicated,
+    ObjectionTooLate,
+    PositionNotRefunded,
 }
 
 impl From<SysError> for Error {
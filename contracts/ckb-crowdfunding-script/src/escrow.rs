@@ -0,0 +1,160 @@
+use core::result::Result;
+
+use super::claim;
+use super::helper;
+use super::tally;
+use super::veto;
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{as_hash, as_u128_be, CrowdfundingInfo, Phase, PledgeInfo, StateInfo},
+    position, since,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    high_level::{load_cell_data, load_cell_lock, load_script, load_script_hash, QueryIter},
+};
+
+// the escrow data recording the finalize transaction and the pledges not refunded yet
+pub const REFUND_RECORD_LEN: usize = 48;
+
+// The escrow cell of a milestone goes to the creator once the state cell has released the milestone.
+// If the project fails or is cancelled, it refunds the backers in proportion to the pledges of their positions,
+// which must be the cells created by the finalize transaction or moved from them.
+// Once the arbitrator has decided to refund the milestone, or the milestone has not been collected
+// by its failed epoch, it refunds the backers whatever the phase, as they take their positions back.
+pub fn validate(
+    crowdfunding_info: &CrowdfundingInfo,
+    receiver_lock_hash: &[u8; 20],
+    state_type_hash: &[u8; 32],
+    milestone: u8,
+) -> Result<(), Error> {
+    if QueryIter::new(load_cell_data, Source::GroupInput).count() != 1 {
+        return Err(Error::EscrowInvalidData);
+    }
+    let state_info = helper::load_current_state_info(state_type_hash)?;
+    let refund_decided = load_cell_data(0, Source::GroupInput)?.len() == REFUND_RECORD_LEN;
+    let failed_epoch = milestone
+        .checked_add(1)
+        .and_then(|release| crowdfunding_info.failed_epoch(release))
+        .ok_or(Error::Encoding)?;
+    match state_info.phase {
+        Phase::Failed | Phase::Cancelled => {
+            validate_refund(crowdfunding_info, state_info.total_pledged)
+        }
+        _ if refund_decided || since::check_group_inputs_since_epoch(failed_epoch) => {
+            validate_refund(crowdfunding_info, state_info.total_pledged)
+        }
+        _ => {
+            if !helper::validate_signature_of_receiver_and_sender(receiver_lock_hash, &[0u8; 20])? {
+                return Err(Error::WrongPubKey);
+            }
+            let state_released = state_info
+                .released(crowdfunding_info.releases())
+                .ok_or(Error::StateInvalidData)?;
+            if state_released < milestone.checked_add(2).ok_or(Error::Encoding)? {
+                return Err(Error::ReleaseTooEarly);
+            }
+            check_milestone_approved(crowdfunding_info, &state_info, milestone)?;
+            let escrow_amount =
+                helper::load_cell_amount(0, Source::GroupInput, &crowdfunding_info.currency)?;
+            if crowdfunding_info.platform_fee.is_some() || !crowdfunding_info.payees.is_empty() {
+                claim::validate_payouts(crowdfunding_info, escrow_amount)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// The milestone is approved by the backers, or not vetoed by them in the veto mode
+pub fn check_milestone_approved(
+    crowdfunding_info: &CrowdfundingInfo,
+    state_info: &StateInfo,
+    milestone: u8,
+) -> Result<(), Error> {
+    let total_pledged = state_info.total_pledged;
+    match crowdfunding_info.veto_threshold {
+        Some(veto_threshold) => veto::check_not_vetoed(
+            crowdfunding_info,
+            veto_threshold,
+            crowdfunding_info.total_vote_weight(state_info),
+            milestone,
+        ),
+        None => tally::check_milestones_approved(
+            crowdfunding_info,
+            &load_script()?,
+            state_info,
+            milestone
+                .checked_add(1)
+                .and_then(|release| crowdfunding_info.released_amount(release, total_pledged))
+                .ok_or(Error::Encoding)?,
+            milestone
+                .checked_add(2)
+                .and_then(|release| crowdfunding_info.released_amount(release, total_pledged))
+                .ok_or(Error::Encoding)?,
+        ),
+    }
+}
+
+// The escrow cell is created without data by the finalize transaction, or records the objections to the milestone,
+// and the rest of it after a refund records the finalize transaction and the pledges not refunded yet.
+fn validate_refund(crowdfunding_info: &CrowdfundingInfo, total_pledged: u128) -> Result<(), Error> {
+    let currency = &crowdfunding_info.currency;
+    let data = load_cell_data(0, Source::GroupInput)?;
+    let (finalize_tx_hash, remaining) = match data.len() {
+        REFUND_RECORD_LEN => (as_hash(&data[0..32]), as_u128_be(&data[32..48])),
+        _ => (
+            veto::Objections::load(0, Source::GroupInput)?.finalize_tx_hash,
+            total_pledged,
+        ),
+    };
+
+    let script = load_script()?;
+    let is_pledge_lock = |lock: &_| helper::is_project_pledge_lock(lock, &script);
+    let proofs = position::load_position_proofs()?;
+    let mut refunded = 0u128;
+    for (index, lock) in QueryIter::new(load_cell_lock, Source::Input).enumerate() {
+        if !is_pledge_lock(&lock)
+            || !position::is_funded_position(
+                &is_pledge_lock,
+                &position::load_input_out_point_of(index)?,
+                &finalize_tx_hash,
+                &proofs,
+                currency.is_some(),
+            )
+        {
+            continue;
+        }
+        let position_info = PledgeInfo::try_from(index, Source::Input, currency.is_some())?;
+        refunded = refunded
+            .checked_add(position_info.pledged)
+            .ok_or(Error::Encoding)?;
+    }
+    if refunded == 0 {
+        return Err(Error::NothingToRefund);
+    }
+    let remaining = remaining
+        .checked_sub(refunded)
+        .ok_or(Error::EscrowInvalidData)?;
+    if remaining == 0 {
+        return Ok(());
+    }
+
+    let escrow_amount = helper::load_cell_amount(0, Source::GroupInput, currency)?;
+    let refund = claim::pro_rata_share(escrow_amount, refunded, remaining + refunded)
+        .ok_or(Error::Encoding)?;
+    let script_hash = load_script_hash()?;
+    let mut escrow_lock_hash = [0u8; 20];
+    escrow_lock_hash.copy_from_slice(&script_hash[0..20]);
+    let index = match helper::position_outputs_by_lock_hash(&escrow_lock_hash)[..] {
+        [index] => index,
+        _ => return Err(Error::EscrowOutputsWrong),
+    };
+    let mut escrow_data = finalize_tx_hash.to_vec();
+    escrow_data.extend_from_slice(&remaining.to_be_bytes());
+    if helper::load_cell_amount(index, Source::Output, currency)? < escrow_amount - refund
+        || load_cell_data(index, Source::Output)? != escrow_data
+    {
+        return Err(Error::EscrowOutputsWrong);
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use core::result::Result;
+
+use super::claim;
+use super::helper;
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{CrowdfundingInfo, Phase, PledgeInfo},
+    hash,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    high_level::{load_cell_data, load_script, load_script_hash},
+};
+
+// The creator finalizes the funding by consolidating the pool into one escrow cell per milestone,
+// so that every milestone release consumes only its escrow cell.
+// The startup amount is released to the creator as the claim does, and every backer keeps the rest of the pledge
+// in one position cell, which records the pledge as the weight of the refunds from the escrow cells.
+pub fn validate(
+    crowdfunding_info: &CrowdfundingInfo,
+    state_type_hash: &[u8; 32],
+    allow_list_proof: &[u8],
+) -> Result<(), Error> {
+    if crowdfunding_info.vesting {
+        return Err(Error::InvalidAction);
+    }
+    let currency = &crowdfunding_info.currency;
+    if helper::load_group_pledge_info(currency.is_some())?.released != 0 {
+        return Err(Error::NothingToRelease);
+    }
+    let state_info = helper::load_current_state_info(state_type_hash)?;
+    if state_info.phase != Phase::Funded {
+        return Err(Error::PledgeNotCounted);
+    }
+    let script = load_script()?;
+    claim::validate_funding(
+        crowdfunding_info,
+        state_type_hash,
+        &script,
+        &state_info,
+        allow_list_proof,
+    )?;
+
+    // every milestone has exactly one escrow cell holding its amount
+    let total_pledged = state_info.total_pledged;
+    for milestone in 0..crowdfunding_info.milestones.len() as u8 {
+        let escrow_lock_hash =
+            hash::blake2b_160(helper::escrow_lock(&script, milestone).as_slice());
+        let (_, amount) = milestone
+            .checked_add(1)
+            .and_then(|release| crowdfunding_info.release(release, total_pledged))
+            .ok_or(Error::Encoding)?;
+        match helper::position_outputs_by_lock_hash(&escrow_lock_hash)[..] {
+            [index]
+                if helper::load_cell_amount(index, Source::Output, currency)? == amount
+                    && load_cell_data(index, Source::Output)?.is_empty() => {}
+            _ => return Err(Error::EscrowOutputsWrong),
+        }
+    }
+
+    // the backer pays all the releases at once
+    let releases = crowdfunding_info.releases();
+    let releases_amount = crowdfunding_info
+        .released_amount(releases, total_pledged)
+        .ok_or(Error::Encoding)?;
+    let group_amount = helper::sum_group_inputs_amount(currency)?;
+    let share = claim::pro_rata_share(group_amount, releases_amount, total_pledged)
+        .ok_or(Error::Encoding)?;
+    let script_hash = load_script_hash()?;
+    let mut crowdfunding_lock_hash = [0u8; 20];
+    crowdfunding_lock_hash.copy_from_slice(&script_hash[0..20]);
+    let position = match helper::position_outputs_by_lock_hash(&crowdfunding_lock_hash)[..] {
+        [index] => index,
+        _ => return Err(Error::ReleaseCapacityWrong),
+    };
+    if helper::load_cell_amount(position, Source::Output, currency)?
+        < group_amount.checked_sub(share).ok_or(Error::Encoding)?
+    {
+        return Err(Error::ReleaseCapacityWrong);
+    }
+    let position_info = PledgeInfo {
+        released: releases,
+        settled: 0,
+        pledged: group_amount,
+    };
+    if PledgeInfo::try_from(position, Source::Output, currency.is_some())? != position_info {
+        return Err(Error::ReleaseCapacityWrong);
+    }
+    // no other position is created with the pledges of the backers
+    if helper::sum_outputs_positions_pledged(&script, currency.is_some())? != total_pledged {
+        return Err(Error::ReleaseCapacityWrong);
+    }
+
+    if crowdfunding_info.platform_fee.is_some() || !crowdfunding_info.payees.is_empty() {
+        let (_, startup_amount) = crowdfunding_info
+            .release(0, total_pledged)
+            .ok_or(Error::Encoding)?;
+        claim::validate_payouts(crowdfunding_info, startup_amount)?;
+    }
+    claim::check_pledge_capacity_conserved(&crowdfunding_lock_hash, currency)
+}
@@ -1,11 +1,22 @@
 use ckb_std::{
     ckb_constants::Source,
     ckb_types::{packed::*, prelude::*},
-    high_level::{load_cell, load_input_since, load_witness_args, QueryIter},
+    error::SysError,
+    high_level::{
+        load_cell, load_cell_capacity, load_cell_data, load_cell_lock, load_cell_type_hash,
+        load_witness_args, QueryIter,
+    },
+    syscalls,
 };
 
-use super::hash;
 use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{
+        isqrt, udt_amount, AttestationInfo, CrowdfundingInfo, PledgeInfo, StateInfo,
+        ATTESTATION_LEN,
+    },
+    hash,
+};
 
 use alloc::vec::Vec;
 use ckb_std::debug;
@@ -15,6 +26,11 @@ const CKB_SUCCESS: i32 = 0;
 #[link(name = "ckb-lib-secp256k1", kind = "static")]
 extern "C" {
     fn validate_secp256k1_blake2b_sighash_all(pubkey_hash: *const u8) -> i32;
+    fn recover_secp256k1_blake2b_message(
+        message: *const u8,
+        signature: *const u8,
+        pubkey_hash: *mut u8,
+    ) -> i32;
 }
 
 pub fn validate_blake2b_signature(pubkey_hash: &mut [u8; 20]) -> Result<(), i32> {
@@ -26,51 +42,402 @@ pub fn validate_blake2b_signature(pubkey_hash: &mut [u8; 20]) -> Result<(), i32>
     Ok(())
 }
 
+pub fn recover_blake2b_message_signer(
+    message: &[u8; 32],
+    signature: &[u8; 65],
+) -> Result<[u8; 20], i32> {
+    let mut pubkey_hash = [0u8; 20];
+    let error_code = unsafe {
+        recover_secp256k1_blake2b_message(
+            message.as_ptr(),
+            signature.as_ptr(),
+            pubkey_hash.as_mut_ptr(),
+        )
+    };
+
+    if error_code != CKB_SUCCESS {
+        return Err(error_code);
+    }
+    Ok(pubkey_hash)
+}
+
 pub fn has_input_by_lock_hash(lock_hash: &[u8; 20]) -> bool {
     QueryIter::new(load_cell, Source::Input)
         .any(|cell| &hash::blake2b_160(cell.lock().as_slice()) == lock_hash)
 }
 
-pub fn position_input_by_lock_hash(lock_hash: &[u8; 20]) -> Option<usize> {
-    QueryIter::new(load_cell, Source::Input)
-        .position(|cell| &hash::blake2b_160(cell.lock().as_slice()) == lock_hash)
+// All the pledge cells of the group must have gone through the same releases
+pub fn load_group_pledge_info(is_udt: bool) -> Result<PledgeInfo, Error> {
+    let mut group_pledge_info = QueryIter::new(load_cell_data, Source::GroupInput)
+        .map(|data| PledgeInfo::from_slice(&data, is_udt));
+    let pledge_info = group_pledge_info.next().ok_or(Error::NoMatchedInputs)?;
+    if group_pledge_info.all(|info| info == pledge_info) {
+        Ok(pledge_info)
+    } else {
+        Err(Error::PledgeReleasedNotSame)
+    }
 }
 
-pub fn load_group_inputs_since() -> Vec<u64> {
-    QueryIter::new(load_input_since, Source::GroupInput).collect::<Vec<_>>()
+// The action is chosen by the first byte of the input_type in the witness of the first group input,
+// and it is the default action (claim or withdraw) if there is none.
+// The rest of the input_type is the args of the action.
+pub fn load_action() -> (u8, Vec<u8>) {
+    let input_type = load_witness_args(0, Source::GroupInput)
+        .ok()
+        .and_then(|witness_args| witness_args.input_type().to_opt())
+        .map(|input_type| input_type.raw_data().to_vec())
+        .unwrap_or_default();
+    match input_type.split_first() {
+        Some((&action, action_args)) => (action, action_args.to_vec()),
+        None => (0, Vec::new()),
+    }
 }
 
-fn add_capacity(c1: u64, c2: u64) -> Result<u64, Error> {
-    match c1.checked_add(c2) {
+fn add_amount(a1: u128, a2: u128) -> Result<u128, Error> {
+    match a1.checked_add(a2) {
         Some(sum) => Ok(sum),
         None => Err(Error::Encoding),
     }
 }
 
-pub fn sum_cells_capacity_of_lock_hash(lock_hash: &[u8; 20], source: Source) -> Result<u64, Error> {
-    QueryIter::new(load_cell, source)
-        .filter(|cell| &hash::blake2b_160(cell.lock().as_slice()) == lock_hash)
-        .try_fold(0, |sum, c| add_capacity(sum, c.capacity().unpack()))
+// The pledges of a project are locked by the crowdfunding lock with the same c-cell, creator and state cell,
+// only the sender in the args (args[52..72]) tells the backers apart.
+pub fn is_same_project_lock(lock: &Script, script: &Script) -> bool {
+    let lock_args = lock.args().raw_data();
+    let script_args = script.args().raw_data();
+    lock.code_hash().as_slice() == script.code_hash().as_slice()
+        && lock.hash_type().as_slice() == script.hash_type().as_slice()
+        && lock_args.len() == script_args.len()
+        && lock_args.len() >= 104
+        && lock_args[0..52] == script_args[0..52]
+        && lock_args[72..104] == script_args[72..104]
 }
 
-pub fn check_witness_args(position: usize) -> Result<(), Error> {
-    match load_witness_args(position, Source::Input) {
-        Ok(witness_args) => {
-            if witness_args.lock().to_opt().is_none() {
-                Err(Error::WitnessSignatureWrong)
-            } else {
-                Ok(())
+// The amount of the cell in the currency of the project,
+// the capacity for the CKB campaigns and the UDT amount for the UDT campaigns.
+pub fn load_cell_amount(
+    index: usize,
+    source: Source,
+    currency: &Option<[u8; 32]>,
+) -> Result<u128, Error> {
+    match currency {
+        None => Ok(load_cell_capacity(index, source)? as u128),
+        Some(udt_type_hash) => {
+            if load_cell_type_hash(index, source)?.as_ref() != Some(udt_type_hash) {
+                return Ok(0);
             }
+            let data = load_cell_data(index, source)?;
+            udt_amount(&data).ok_or(Error::Encoding)
         }
-        Err(_) => Err(Error::WitnessSignatureWrong),
     }
 }
 
+fn sum_cells_amount<F: Fn(&Script) -> bool>(
+    source: Source,
+    currency: &Option<[u8; 32]>,
+    filter: F,
+) -> Result<u128, Error> {
+    QueryIter::new(load_cell_lock, source)
+        .enumerate()
+        .filter(|(_, lock)| filter(lock))
+        .try_fold(0, |sum, (index, _)| {
+            add_amount(sum, load_cell_amount(index, source, currency)?)
+        })
+}
+
+pub fn sum_cells_amount_of_lock_hash(
+    lock_hash: &[u8; 20],
+    source: Source,
+    currency: &Option<[u8; 32]>,
+) -> Result<u128, Error> {
+    sum_cells_amount(source, currency, |lock| {
+        &hash::blake2b_160(lock.as_slice()) == lock_hash
+    })
+}
+
+// The escrow cell of a milestone is locked by the crowdfunding lock of the project without a sender,
+// and with the milestone index after the args of the pledges.
+// The tally cell of a milestone has one more byte of 0 after the args of the escrow cell,
+// and the proposal cell of the amendment of a milestone one more byte of 1.
+pub const PLEDGE_ARGS_LEN: usize = 104;
+pub const ESCROW_ARGS_LEN: usize = 105;
+pub const TALLY_ARGS_LEN: usize = 106;
+pub const AMENDMENT_KIND: u8 = 1;
+
+// Any escrow cell of the project in the inputs
+pub fn has_escrow_input(script: &Script) -> bool {
+    QueryIter::new(load_cell_lock, Source::Input).any(|lock| {
+        let lock_args = lock.args().raw_data();
+        lock_args.len() == ESCROW_ARGS_LEN
+            && lock.as_slice() == escrow_lock(script, lock_args[104]).as_slice()
+    })
+}
+
+pub fn escrow_lock(script: &Script, milestone: u8) -> Script {
+    let args = script.args().raw_data();
+    let mut escrow_args = Vec::with_capacity(ESCROW_ARGS_LEN);
+    escrow_args.extend_from_slice(&args[0..52]);
+    escrow_args.extend_from_slice(&[0u8; 20]);
+    escrow_args.extend_from_slice(&args[72..104]);
+    escrow_args.push(milestone);
+    script
+        .clone()
+        .as_builder()
+        .args(escrow_args.as_slice().pack())
+        .build()
+}
+
+pub fn tally_lock(script: &Script, milestone: u8) -> Script {
+    escrow_lock_of_kind(script, milestone, 0)
+}
+
+pub fn amendment_lock(script: &Script, milestone: u8) -> Script {
+    escrow_lock_of_kind(script, milestone, AMENDMENT_KIND)
+}
+
+fn escrow_lock_of_kind(script: &Script, milestone: u8, kind: u8) -> Script {
+    let escrow_lock = escrow_lock(script, milestone);
+    let mut args = escrow_lock.args().raw_data().to_vec();
+    args.push(kind);
+    escrow_lock
+        .as_builder()
+        .args(args.as_slice().pack())
+        .build()
+}
+
+// Whether the lock is a pledge of the project of the script, which is a pledge or an escrow cell
+pub fn is_project_pledge_lock(lock: &Script, script: &Script) -> bool {
+    let lock_args = lock.args().raw_data();
+    let script_args = script.args().raw_data();
+    lock.code_hash().as_slice() == script.code_hash().as_slice()
+        && lock.hash_type().as_slice() == script.hash_type().as_slice()
+        && lock_args.len() == PLEDGE_ARGS_LEN
+        && script_args.len() >= PLEDGE_ARGS_LEN
+        && lock_args[0..52] == script_args[0..52]
+        && lock_args[72..104] == script_args[72..104]
+}
+
+pub fn sum_project_inputs_amount(
+    script: &Script,
+    currency: &Option<[u8; 32]>,
+) -> Result<u128, Error> {
+    sum_cells_amount(Source::Input, currency, |lock| {
+        is_same_project_lock(lock, script)
+    })
+}
+
+// The sender (backer) in args[52..72] of a pledge lock of the project
+pub fn pledge_sender_lock_hash(lock: &Script) -> [u8; 20] {
+    let mut sender_lock_hash = [0u8; 20];
+    sender_lock_hash.copy_from_slice(&lock.args().raw_data()[52..72]);
+    sender_lock_hash
+}
+
+pub fn sum_project_cells_amount_of_sender(
+    script: &Script,
+    sender_lock_hash: &[u8; 20],
+    source: Source,
+    currency: &Option<[u8; 32]>,
+) -> Result<u128, Error> {
+    sum_cells_amount(source, currency, |lock| {
+        is_same_project_lock(lock, script) && &pledge_sender_lock_hash(lock) == sender_lock_hash
+    })
+}
+
+// The pledges of every backer in the inputs, summed by the sender lock hash
+fn sum_inputs_pledges_of_backers(
+    script: &Script,
+    currency: &Option<[u8; 32]>,
+) -> Result<Vec<([u8; 20], u128)>, Error> {
+    let mut backers: Vec<([u8; 20], u128)> = Vec::new();
+    for (index, lock) in QueryIter::new(load_cell_lock, Source::Input).enumerate() {
+        if !is_same_project_lock(&lock, script) {
+            continue;
+        }
+        let amount = load_cell_amount(index, Source::Input, currency)?;
+        let sender_lock_hash = pledge_sender_lock_hash(&lock);
+        match backers
+            .iter_mut()
+            .find(|(hash, _)| hash == &sender_lock_hash)
+        {
+            Some((_, sum)) => *sum = add_amount(*sum, amount)?,
+            None => backers.push((sender_lock_hash, amount)),
+        }
+    }
+    Ok(backers)
+}
+
+// The quadratic weight of the backers in the inputs, the sum of the integer square roots of their pledges
+pub fn sum_inputs_quadratic_weight(
+    script: &Script,
+    currency: &Option<[u8; 32]>,
+) -> Result<u128, Error> {
+    sum_inputs_pledges_of_backers(script, currency)?
+        .into_iter()
+        .try_fold(0u128, |sum, (_, amount)| add_amount(sum, isqrt(amount)))
+}
+
+// The pledges recorded by the positions of the project in the outputs,
+// which must be the pledges of the backers finalized, as the votes weigh them
+pub fn sum_outputs_positions_pledged(script: &Script, is_udt: bool) -> Result<u128, Error> {
+    let mut pledged = 0u128;
+    for (index, lock) in QueryIter::new(load_cell_lock, Source::Output).enumerate() {
+        if is_project_pledge_lock(&lock, script) {
+            pledged = add_amount(
+                pledged,
+                PledgeInfo::try_from(index, Source::Output, is_udt)?.pledged,
+            )?;
+        }
+    }
+    Ok(pledged)
+}
+
+// The pledges counted when the project gets funded must be within the limits of every backer
+pub fn check_inputs_pledge_limits(
+    script: &Script,
+    crowdfunding_info: &CrowdfundingInfo,
+) -> Result<(), Error> {
+    for (_, amount) in sum_inputs_pledges_of_backers(script, &crowdfunding_info.currency)? {
+        if amount < crowdfunding_info.min_pledge {
+            return Err(Error::PledgeBelowMinimum);
+        }
+        if amount > crowdfunding_info.max_pledge {
+            return Err(Error::PledgeAboveMaximum);
+        }
+    }
+    Ok(())
+}
+
+// The backer presents an attestation cell in the cell deps signed by the issuer to the project,
+// and it must not expire before the funding epoch.
+pub fn check_backer_attestation(
+    kyc_issuer: &[u8; 20],
+    project_id: &[u8],
+    backer: &[u8; 20],
+    funding_epoch: u64,
+) -> Result<(), Error> {
+    let mut attested = false;
+    let mut index = 0;
+    loop {
+        let mut data = [0u8; ATTESTATION_LEN];
+        match syscalls::load_cell_data(&mut data, 0, index, Source::CellDep) {
+            Ok(ATTESTATION_LEN) => {
+                let attestation = AttestationInfo::from_slice(&data)?;
+                if &attestation.backer == backer
+                    && recover_blake2b_message_signer(
+                        &attestation.message(project_id, kyc_issuer),
+                        &attestation.signature,
+                    )
+                    .map_or(false, |signer| &signer == kyc_issuer)
+                {
+                    if attestation.expiry >= funding_epoch {
+                        return Ok(());
+                    }
+                    attested = true;
+                }
+            }
+            Ok(_) | Err(SysError::LengthNotEnough(_)) => {}
+            Err(SysError::IndexOutOfBound) => break,
+            Err(err) => return Err(err.into()),
+        }
+        index += 1;
+    }
+    if attested {
+        Err(Error::AttestationExpired)
+    } else {
+        Err(Error::BackerNotAttested)
+    }
+}
+
+pub fn sum_group_inputs_amount(currency: &Option<[u8; 32]>) -> Result<u128, Error> {
+    sum_cells_amount(Source::GroupInput, currency, |_| true)
+}
+
+pub fn position_outputs_by_lock_hash(lock_hash: &[u8; 20]) -> Vec<usize> {
+    QueryIter::new(load_cell_lock, Source::Output)
+        .enumerate()
+        .filter(|(_, lock)| &hash::blake2b_160(lock.as_slice()) == lock_hash)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+pub fn load_state_info(
+    state_type_hash: &[u8; 32],
+    source: Source,
+) -> Result<Option<StateInfo>, Error> {
+    match QueryIter::new(load_cell_type_hash, source)
+        .position(|type_hash| type_hash.as_ref() == Some(state_type_hash))
+    {
+        Some(index) => Ok(Some(
+            StateInfo::try_from(index, source).map_err(|_| Error::StateInvalidData)?,
+        )),
+        None => Ok(None),
+    }
+}
+
+// The milestone times amended in the current state of the project,
+// only the amendments of the proposal cells of the lock have been approved by the backers
+pub fn apply_amendments(
+    crowdfunding_info: &mut CrowdfundingInfo,
+    script: &Script,
+    state_type_hash: &[u8; 32],
+) -> Result<(), Error> {
+    let state_info = match load_state_info(state_type_hash, Source::Output)? {
+        Some(state_info) => state_info,
+        None => match load_state_info(state_type_hash, Source::CellDep)? {
+            Some(state_info) => state_info,
+            None => return Ok(()),
+        },
+    };
+    let amendments: Vec<_> = state_info
+        .amendments
+        .into_iter()
+        .filter(|amendment| {
+            amendment.proposal
+                == hash::blake2b_256(amendment_lock(script, amendment.milestone).as_slice())
+        })
+        .collect();
+    crowdfunding_info.amend(&amendments);
+    Ok(())
+}
+
+// The current state of the project is the output state cell if the transaction updates it,
+// otherwise it is the state cell in the cell deps.
+pub fn load_current_state_info(state_type_hash: &[u8; 32]) -> Result<StateInfo, Error> {
+    match load_state_info(state_type_hash, Source::Output)? {
+        Some(state_info) => Ok(state_info),
+        None => load_state_info(state_type_hash, Source::CellDep)?.ok_or(Error::NoStateCell),
+    }
+}
+
+pub fn check_outputs_pledge_info(
+    lock_hash: &[u8; 20],
+    pledge_info: &PledgeInfo,
+    is_udt: bool,
+) -> Result<bool, Error> {
+    for index in position_outputs_by_lock_hash(lock_hash) {
+        if &PledgeInfo::try_from(index, Source::Output, is_udt)? != pledge_info {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
 const TYPE: u8 = 1;
 const CODE_HASH_SECP256K1_BLAKE160: [u8; 32] = [
     155, 215, 224, 111, 62, 207, 75, 224, 242, 252, 210, 24, 139, 35, 241, 185, 252, 200, 142, 93,
     75, 101, 168, 99, 123, 23, 114, 59, 189, 163, 204, 232,
 ];
+// The lock hash 160 of the default secp256k1 lock of the public key hash
+pub fn secp256k1_lock_hash(public_key_hash: &[u8; 20]) -> [u8; 20] {
+    let lock_script = Script::new_builder()
+        .code_hash(CODE_HASH_SECP256K1_BLAKE160.pack())
+        .args(public_key_hash.pack())
+        .hash_type(Byte::new(TYPE))
+        .build();
+    hash::blake2b_160(lock_script.as_slice())
+}
+
 // Recover public key from the signature
 // and check whether the public key belongs to the receiver or the sender.
 pub fn validate_signature_of_receiver_and_sender(
@@ -82,12 +449,7 @@ pub fn validate_signature_of_receiver_and_sender(
     validate_blake2b_signature(&mut public_key_hash).map_err(|_| Error::Secp256k1)?;
     debug!("public_key_hash: {:?}", public_key_hash);
 
-    let lock_script = Script::new_builder()
-        .code_hash(CODE_HASH_SECP256K1_BLAKE160.pack())
-        .args(public_key_hash.pack())
-        .hash_type(Byte::new(TYPE))
-        .build();
-    let lock_hash = hash::blake2b_160(lock_script.as_slice());
+    let lock_hash = secp256k1_lock_hash(&public_key_hash);
 
     debug!("lock_hash: {:?}", lock_hash);
 
@@ -12,12 +12,19 @@
 #![feature(panic_info_message)]
 
 // define modules
+mod aggregate;
+mod amend;
+mod arbitrate;
 mod claim;
-mod data;
+mod council;
 mod entry;
 mod error;
-mod hash;
+mod escrow;
+mod finalize;
 mod helper;
+mod tally;
+mod transfer;
+mod veto;
 mod withdraw;
 
 use ckb_std::default_alloc;
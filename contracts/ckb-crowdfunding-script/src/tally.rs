@@ -0,0 +1,335 @@
+use core::result::Result;
+
+use alloc::vec::Vec;
+
+use super::{council, helper};
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{
+        CrowdfundingInfo, DelegationInfo, MilestoneInfo, StateInfo, TallyInfo, VoteInfo, VoteType,
+        COMMITMENT_LEN, DELEGATION_LEN, REVEAL_WINDOW_EPOCHS, TALLY_LEN,
+    },
+    hash, position, since,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{
+        bytes::Bytes,
+        packed::{Byte, Script},
+        prelude::*,
+    },
+    high_level::{
+        load_cell_data, load_cell_lock_hash, load_cell_type_hash, load_header, QueryIter,
+    },
+};
+
+// The milestones released between the two released amounts must have been approved by the backers.
+// A milestone is approved when the votes cast reach its quorum of the total pledged
+// and the votes in favor reach its approval ratio,
+// where the vote cells of the project are the cell deps,
+// and every vote weighs no more than the position of the backer created by the funding transaction
+// in the inputs or the cell deps, so the votes of the positions refunded do not count.
+// The delegation cells in the cell deps add the weight of the delegators to the votes of their delegates,
+// unless the delegators have voted directly, and a backer revokes a delegation by consuming its cell.
+// In the commit-reveal voting, only the votes revealed from the commitments count,
+// not the tallies whose signed votes are public.
+// They are tallied after the reveal window since the time of the milestone,
+// and only those revealed within it, proven by the headers of their blocks in the header deps, count.
+// The votes against left out of the cell deps cannot be told from the abstentions,
+// so the ratio of the vote cells is of the total pledged, as if all the others were against.
+// A confirmed tally cell of the milestone in the cell deps counts instead,
+// and its ratio is of the votes cast as a vote left out of it could have been challenged.
+// In the quadratic voting, a vote weighs the integer square root of the position instead,
+// and the total weight recorded when the project got funded takes the place of the total pledged.
+// In the council mode, the council approves the milestones instead of the backers.
+pub fn check_milestones_approved(
+    crowdfunding_info: &CrowdfundingInfo,
+    script: &Script,
+    state_info: &StateInfo,
+    released_from: u128,
+    released_to: u128,
+) -> Result<(), Error> {
+    let vote_type_hash = match (
+        &crowdfunding_info.vote_type,
+        crowdfunding_info.veto_threshold,
+    ) {
+        (None, None) if crowdfunding_info.council.is_none() => return Ok(()),
+        (vote_type, _) => vote_type
+            .as_ref()
+            .map(|vote_type| vote_type_hash(vote_type, script)),
+    };
+    let total_pledged = state_info.total_pledged;
+    let total_weight = crowdfunding_info.total_vote_weight(state_info);
+    let mut weights = None;
+    for (index, milestone) in crowdfunding_info.milestones.iter().enumerate() {
+        let start = (index as u8)
+            .checked_add(1)
+            .and_then(|release| crowdfunding_info.released_amount(release, total_pledged))
+            .ok_or(Error::Encoding)?;
+        let end = (index as u8)
+            .checked_add(2)
+            .and_then(|release| crowdfunding_info.released_amount(release, total_pledged))
+            .ok_or(Error::Encoding)?;
+        if start >= released_to || end <= released_from {
+            continue;
+        }
+        // the objections of the veto mode are counted only in the escrow cells
+        if crowdfunding_info.veto_threshold.is_some() {
+            return Err(Error::MilestoneNotEscrowed);
+        }
+        if let Some(council) = &crowdfunding_info.council {
+            council::check_council_approved(council, script, index as u8)?;
+            continue;
+        }
+        let vote_type_hash = match &vote_type_hash {
+            Some(vote_type_hash) => vote_type_hash,
+            None => continue,
+        };
+        if milestone.approval_ratio_threshold == 0 && milestone.quorum == 0 {
+            continue;
+        }
+        if crowdfunding_info.commit_reveal
+            && !since::check_group_inputs_since_epoch(
+                milestone.time.saturating_add(REVEAL_WINDOW_EPOCHS),
+            )
+        {
+            return Err(Error::ReleaseTooEarly);
+        }
+        if weights.is_none() {
+            weights = Some(load_position_weights(crowdfunding_info, script)?);
+        }
+        let (votes, tallies) = tally_votes(
+            crowdfunding_info,
+            script,
+            vote_type_hash,
+            index as u8,
+            None,
+            weights.as_deref().unwrap_or_default(),
+        )?;
+        let mut result = check_votes(milestone, &votes, total_weight, total_weight);
+        for tally in tallies {
+            if result.is_ok() {
+                break;
+            }
+            result = check_votes(milestone, &tally, tally.cast, total_weight);
+        }
+        result?;
+    }
+    Ok(())
+}
+
+// The votes on the amendment of a milestone to the new time are counted as the votes on the milestone,
+// and they must reach the approval ratio and the quorum of the milestone.
+// The votes on the amendments are public in the commit-reveal voting too.
+pub fn check_amendment_approved(
+    crowdfunding_info: &CrowdfundingInfo,
+    script: &Script,
+    state_info: &StateInfo,
+    milestone: u8,
+    time: u64,
+) -> Result<(), Error> {
+    let vote_type = crowdfunding_info
+        .vote_type
+        .as_ref()
+        .ok_or(Error::InvalidAction)?;
+    let milestone_info = crowdfunding_info
+        .milestones
+        .get(milestone as usize)
+        .ok_or(Error::AmendmentWrong)?;
+    let total_weight = crowdfunding_info.total_vote_weight(state_info);
+    let (votes, _) = tally_votes(
+        crowdfunding_info,
+        script,
+        &vote_type_hash(vote_type, script),
+        milestone,
+        Some(time),
+        &load_position_weights(crowdfunding_info, script)?,
+    )?;
+    check_votes(milestone_info, &votes, total_weight, total_weight)
+}
+
+// The votes in favor must reach the approval ratio of the base, and the votes cast the quorum of the total weight
+fn check_votes(
+    milestone: &MilestoneInfo,
+    votes: &Votes,
+    base: u128,
+    total_weight: u128,
+) -> Result<(), Error> {
+    if votes.cast.checked_mul(10).ok_or(Error::Encoding)?
+        < total_weight
+            .checked_mul(milestone.quorum as u128)
+            .ok_or(Error::Encoding)?
+    {
+        return Err(Error::MilestoneQuorumNotReached);
+    }
+    if votes.approved.checked_mul(10).ok_or(Error::Encoding)?
+        < base
+            .checked_mul(milestone.approval_ratio_threshold as u128)
+            .ok_or(Error::Encoding)?
+    {
+        return Err(Error::MilestoneNotApproved);
+    }
+    Ok(())
+}
+
+// The vote cells of a project have the type script with the args of the project id
+// and the code hash and the hash type of the crowdfunding lock
+pub fn vote_type_hash(vote_type: &VoteType, script: &Script) -> [u8; 32] {
+    let mut args = Vec::with_capacity(65);
+    args.extend_from_slice(&script.args().raw_data()[0..32]);
+    args.extend_from_slice(script.code_hash().as_slice());
+    args.extend_from_slice(script.hash_type().as_slice());
+    let vote_type_script = Script::new_builder()
+        .code_hash(vote_type.code_hash.pack())
+        .hash_type(Byte::new(vote_type.hash_type))
+        .args(Bytes::from(args).pack())
+        .build();
+    hash::blake2b_256(vote_type_script.as_slice())
+}
+
+struct Votes {
+    approved: u128, // the weight of the votes in favor
+    cast: u128,     // the weight of the votes cast
+}
+
+impl Votes {
+    fn add(&mut self, weight: u128, approve: bool) -> Result<(), Error> {
+        self.cast = self.cast.checked_add(weight).ok_or(Error::Encoding)?;
+        if approve {
+            self.approved = self.approved.checked_add(weight).ok_or(Error::Encoding)?;
+        }
+        Ok(())
+    }
+}
+
+// Return the votes of the vote cells and the votes of the confirmed tallies,
+// on the milestone or on its amendment to the new time.
+// A backer is counted once for the milestone, whatever number of vote cells it has,
+// and a delegation is never followed past the delegate, so the counting is linear in the cell deps.
+// A backer reveals one vote on a milestone, so the votes revealed twice are rejected,
+// even when one of them is revealed after the window or the commitments are made in different transactions,
+// and leaving one of them out counts no more than a commitment not revealed.
+// A backer delegates to one backer, so the delegations of the same delegator are rejected
+// instead of counting the one of them picked by the transaction.
+fn tally_votes(
+    crowdfunding_info: &CrowdfundingInfo,
+    script: &Script,
+    vote_type_hash: &[u8; 32],
+    milestone: u8,
+    amendment: Option<u64>,
+    weights: &[([u8; 20], u128)],
+) -> Result<(Votes, Vec<Votes>), Error> {
+    let tally_lock_hash = hash::blake2b_256(helper::tally_lock(script, milestone).as_slice());
+    let revealed = crowdfunding_info.commit_reveal && amendment.is_none();
+    let reveal_end = crowdfunding_info
+        .milestones
+        .get(milestone as usize)
+        .ok_or(Error::Encoding)?
+        .time
+        .saturating_add(REVEAL_WINDOW_EPOCHS);
+    let mut voters: Vec<([u8; 20], bool)> = Vec::new();
+    let mut revealers: Vec<[u8; 20]> = Vec::new();
+    let mut delegations: Vec<DelegationInfo> = Vec::new();
+    let mut votes = Votes {
+        approved: 0,
+        cast: 0,
+    };
+    let mut tallies = Vec::new();
+    for (index, type_hash) in QueryIter::new(load_cell_type_hash, Source::CellDep).enumerate() {
+        if type_hash.as_ref() != Some(vote_type_hash) {
+            continue;
+        }
+        let data = load_cell_data(index, Source::CellDep)?;
+        if data.len() == TALLY_LEN {
+            let tally = TallyInfo::from_slice(&data).map_err(|_| Error::TallyInvalidData)?;
+            if tally.confirmed
+                && amendment.is_none()
+                && !crowdfunding_info.commit_reveal
+                && load_cell_lock_hash(index, Source::CellDep)? == tally_lock_hash
+            {
+                tallies.push(Votes {
+                    approved: tally.approved,
+                    cast: tally.cast,
+                });
+            }
+            continue;
+        }
+        if data.len() == DELEGATION_LEN {
+            let delegation =
+                DelegationInfo::from_slice(&data).map_err(|_| Error::VoteInvalidData)?;
+            if delegations
+                .iter()
+                .any(|other| other.delegator == delegation.delegator)
+            {
+                return Err(Error::DelegationDuplicated);
+            }
+            delegations.push(delegation);
+            continue;
+        }
+        if data.len() == COMMITMENT_LEN {
+            continue;
+        }
+        let vote = VoteInfo::from_slice(&data).map_err(|_| Error::VoteInvalidData)?;
+        if vote.milestone != milestone
+            || vote.amendment != amendment
+            || (revealed && vote.commitment.is_none())
+        {
+            continue;
+        }
+        if revealed {
+            if revealers.contains(&vote.backer) {
+                return Err(Error::CommitmentDuplicated);
+            }
+            revealers.push(vote.backer);
+            let revealed_in_window = load_header(index, Source::CellDep).map_or(false, |header| {
+                since::epoch_number(header.raw().epoch().unpack()) < reveal_end
+            });
+            if !revealed_in_window {
+                continue;
+            }
+        }
+        if voters.iter().any(|(voter, _)| voter == &vote.backer) {
+            continue;
+        }
+        voters.push((vote.backer, vote.approve));
+        let weight = position::weight_of(weights, &vote.backer);
+        votes.add(
+            vote.weight.min(crowdfunding_info.vote_weight(weight)),
+            vote.approve,
+        )?;
+    }
+    for delegation in delegations {
+        let approve = match voters
+            .iter()
+            .find(|(voter, _)| voter == &delegation.delegate)
+        {
+            Some((_, approve)) => *approve,
+            None => continue,
+        };
+        if voters
+            .iter()
+            .any(|(voter, _)| voter == &delegation.delegator)
+        {
+            continue;
+        }
+        let weight = position::weight_of(weights, &delegation.delegator);
+        votes.add(crowdfunding_info.vote_weight(weight), approve)?;
+    }
+    Ok((votes, tallies))
+}
+
+// The weights of the backers from their positions created by the funding transaction, ordered by the backers.
+// The pledges of a project counting the votes must have been consolidated into the escrow cells,
+// as the other cells of the pledge lock can be created by anyone with any data.
+pub fn load_position_weights(
+    crowdfunding_info: &CrowdfundingInfo,
+    script: &Script,
+) -> Result<Vec<([u8; 20], u128)>, Error> {
+    let funding_tx_hash = position::load_funding_tx_hash(&script.args().raw_data()[72..104])?
+        .ok_or(Error::MilestoneNotEscrowed)?;
+    Ok(position::load_position_weights(
+        |lock| helper::is_project_pledge_lock(lock, script),
+        &funding_tx_hash,
+        crowdfunding_info.currency.is_some(),
+    )?)
+}
@@ -0,0 +1,105 @@
+use core::result::Result;
+
+use super::helper;
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{CrowdfundingInfo, PledgeInfo},
+    position,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{packed::Script, prelude::*},
+    high_level::{load_cell_lock, load_script, QueryIter},
+};
+
+// The backer sells the position by re-locking all the pledges of the group to a new backer,
+// with the same project and creator args, the same capacity (and UDT amount) and the same releases,
+// so the new backer gets the refund and voting rights of the pledges.
+pub fn validate(
+    crowdfunding_info: &CrowdfundingInfo,
+    sender_lock_hash: &[u8; 20],
+    state_type_hash: &[u8; 32],
+) -> Result<(), Error> {
+    let script = load_script()?;
+    let is_udt = crowdfunding_info.currency.is_some();
+    let pledge_info = helper::load_group_pledge_info(is_udt)?;
+    if pledge_info.pledged > 0 {
+        check_positions_moved(&script, state_type_hash, is_udt)?;
+    }
+
+    let mut new_sender_lock_hash: Option<[u8; 20]> = None;
+    for (index, lock) in QueryIter::new(load_cell_lock, Source::Output).enumerate() {
+        if !helper::is_same_project_lock(&lock, &script) {
+            continue;
+        }
+        let lock_hash = helper::pledge_sender_lock_hash(&lock);
+        if &lock_hash == sender_lock_hash
+            || new_sender_lock_hash.map_or(false, |hash| hash != lock_hash)
+        {
+            return Err(Error::TransferOutputsWrong);
+        }
+        new_sender_lock_hash = Some(lock_hash);
+        if PledgeInfo::try_from(index, Source::Output, is_udt)? != pledge_info {
+            return Err(Error::TransferOutputsWrong);
+        }
+    }
+    let new_sender_lock_hash = new_sender_lock_hash.ok_or(Error::TransferOutputsWrong)?;
+
+    // None checks the capacity, the pledges the new backer already has in the inputs stay as they are
+    for currency in [None, crowdfunding_info.currency].iter() {
+        let transferred_amount = helper::sum_project_cells_amount_of_sender(
+            &script,
+            &new_sender_lock_hash,
+            Source::Output,
+            currency,
+        )?;
+        let new_sender_inputs_amount = helper::sum_project_cells_amount_of_sender(
+            &script,
+            &new_sender_lock_hash,
+            Source::Input,
+            currency,
+        )?;
+        if transferred_amount.checked_sub(new_sender_inputs_amount)
+            != Some(helper::sum_group_inputs_amount(currency)?)
+        {
+            return Err(Error::TransferCapacityWrong);
+        }
+    }
+    Ok(())
+}
+
+// A finalized position moves whole with the proof of its funding, and the positions created by the transaction
+// carry over the pledges of the funded positions it consumes, so the moved ones are proved by this transaction.
+fn check_positions_moved(
+    script: &Script,
+    state_type_hash: &[u8; 32],
+    is_udt: bool,
+) -> Result<(), Error> {
+    let funding_tx_hash =
+        position::load_funding_tx_hash(state_type_hash)?.ok_or(Error::MilestoneNotEscrowed)?;
+    let is_pledge_lock = |lock: &Script| helper::is_project_pledge_lock(lock, script);
+    let proofs = position::load_position_proofs()?;
+    let mut funded_pledged = 0u128;
+    for (index, lock) in QueryIter::new(load_cell_lock, Source::Input).enumerate() {
+        if !is_pledge_lock(&lock) {
+            continue;
+        }
+        if position::is_funded_position(
+            &is_pledge_lock,
+            &position::load_input_out_point_of(index)?,
+            &funding_tx_hash,
+            &proofs,
+            is_udt,
+        ) {
+            funded_pledged = funded_pledged
+                .checked_add(PledgeInfo::try_from(index, Source::Input, is_udt)?.pledged)
+                .ok_or(Error::Encoding)?;
+        } else if lock.as_slice() == script.as_slice() {
+            return Err(Error::TransferOutputsWrong);
+        }
+    }
+    if helper::sum_outputs_positions_pledged(script, is_udt)? != funded_pledged {
+        return Err(Error::TransferOutputsWrong);
+    }
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use core::result::Result;
+
+use alloc::vec::Vec;
+
+use super::{helper, tally};
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{
+        as_hash, as_u128_be, CrowdfundingInfo, Phase, VoteInfo, COMMITMENT_LEN, DELEGATION_LEN,
+        TALLY_LEN, VETO_WINDOW_EPOCHS,
+    },
+    position, since,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    high_level::{
+        load_cell_data, load_cell_type_hash, load_header, load_input_out_point, load_script,
+        load_script_hash, QueryIter,
+    },
+};
+
+// The objections to the milestone counted in its escrow cell
+pub struct Objections {
+    pub finalize_tx_hash: [u8; 32],
+    pub objected: u128,         // the weight of the objections
+    pub backers: Vec<[u8; 20]>, // the backers objecting, each counted once
+}
+
+impl Objections {
+    // The escrow cell is created without data by the finalize transaction,
+    // and the objections are recorded with the finalize transaction, so the refunds still find the positions
+    pub fn load(index: usize, source: Source) -> Result<Self, Error> {
+        let data = load_cell_data(index, source)?;
+        if data.is_empty() {
+            let out_point = load_input_out_point(index, source)?;
+            return Ok(Objections {
+                finalize_tx_hash: as_hash(out_point.tx_hash().as_slice()),
+                objected: 0,
+                backers: Vec::new(),
+            });
+        }
+        if data.len() <= 48 || (data.len() - 48) % 20 != 0 {
+            return Err(Error::EscrowInvalidData);
+        }
+        Ok(Objections {
+            finalize_tx_hash: as_hash(&data[0..32]),
+            objected: as_u128_be(&data[32..48]),
+            backers: data[48..]
+                .chunks_exact(20)
+                .map(|backer| {
+                    let mut lock_hash = [0u8; 20];
+                    lock_hash.copy_from_slice(backer);
+                    lock_hash
+                })
+                .collect(),
+        })
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        let mut data = self.finalize_tx_hash.to_vec();
+        data.extend_from_slice(&self.objected.to_be_bytes());
+        for backer in &self.backers {
+            data.extend_from_slice(backer);
+        }
+        data
+    }
+}
+
+// escrow data with objections: finalize tx hash 32 bytes, objected weight 16 bytes, backers 20 bytes each
+
+// In the veto mode, the objections are the vote cells against the milestone in the cell deps.
+// Anyone can count them into the escrow cell of the milestone before it is released,
+// every objection weighs no more than the position of the backer as the votes do,
+// or their integer square root in the quadratic voting,
+// and the escrow cell is recreated with the same capacity and the objections added.
+// The objections are counted within the veto window, with the group inputs locked by an absolute epoch since
+// before its end, and only the votes cast before its end count, proven by the headers of their blocks
+// in the header deps, so the objections seen after the window never stop the release.
+pub fn validate_objection(
+    crowdfunding_info: &CrowdfundingInfo,
+    state_type_hash: &[u8; 32],
+    milestone: u8,
+) -> Result<(), Error> {
+    let vote_type = match (
+        &crowdfunding_info.vote_type,
+        crowdfunding_info.veto_threshold,
+    ) {
+        (Some(vote_type), Some(_)) => vote_type,
+        _ => return Err(Error::InvalidAction),
+    };
+    if QueryIter::new(load_cell_data, Source::GroupInput).count() != 1 {
+        return Err(Error::EscrowInvalidData);
+    }
+    let state_info = helper::load_current_state_info(state_type_hash)?;
+    if state_info.phase == Phase::Failed || state_info.phase == Phase::Cancelled {
+        return Err(Error::InvalidAction);
+    }
+    let window_end = veto_window_end(crowdfunding_info, milestone)?;
+    if since::group_inputs_since_epoch().map_or(true, |epoch| epoch >= window_end) {
+        return Err(Error::ObjectionTooLate);
+    }
+
+    let script = load_script()?;
+    let vote_type_hash = tally::vote_type_hash(vote_type, &script);
+    let mut objections = Objections::load(0, Source::GroupInput)?;
+    let objected = objections.objected;
+    let weights = tally::load_position_weights(crowdfunding_info, &script)?;
+    for (index, type_hash) in QueryIter::new(load_cell_type_hash, Source::CellDep).enumerate() {
+        if type_hash.as_ref() != Some(&vote_type_hash) {
+            continue;
+        }
+        let data = load_cell_data(index, Source::CellDep)?;
+        // every backer objects by itself, the delegations count only in the approvals
+        if data.len() == TALLY_LEN || data.len() == DELEGATION_LEN || data.len() == COMMITMENT_LEN {
+            continue;
+        }
+        let vote = VoteInfo::from_slice(&data).map_err(|_| Error::VoteInvalidData)?;
+        if vote.milestone != milestone
+            || vote.approve
+            || vote.amendment.is_some()
+            || objections.backers.contains(&vote.backer)
+        {
+            continue;
+        }
+        let cast_in_window = load_header(index, Source::CellDep).map_or(false, |header| {
+            since::epoch_number(header.raw().epoch().unpack()) < window_end
+        });
+        if !cast_in_window {
+            continue;
+        }
+        let weight = crowdfunding_info.vote_weight(position::weight_of(&weights, &vote.backer));
+        objections.backers.push(vote.backer);
+        objections.objected = objections
+            .objected
+            .checked_add(vote.weight.min(weight))
+            .ok_or(Error::Encoding)?;
+    }
+    if objections.objected == objected {
+        return Err(Error::ObjectionsWrong);
+    }
+
+    let currency = &crowdfunding_info.currency;
+    let script_hash = load_script_hash()?;
+    let mut escrow_lock_hash = [0u8; 20];
+    escrow_lock_hash.copy_from_slice(&script_hash[0..20]);
+    let index = match helper::position_outputs_by_lock_hash(&escrow_lock_hash)[..] {
+        [index] => index,
+        _ => return Err(Error::EscrowOutputsWrong),
+    };
+    if helper::load_cell_amount(index, Source::Output, currency)?
+        < helper::load_cell_amount(0, Source::GroupInput, currency)?
+        || load_cell_data(index, Source::Output)? != objections.to_vec()
+    {
+        return Err(Error::EscrowOutputsWrong);
+    }
+    Ok(())
+}
+
+// The milestone is released after the veto window if the objections have not reached the veto threshold
+// of the total weight of the backers
+pub fn check_not_vetoed(
+    crowdfunding_info: &CrowdfundingInfo,
+    veto_threshold: u8,
+    total_weight: u128,
+    milestone: u8,
+) -> Result<(), Error> {
+    let window_end = veto_window_end(crowdfunding_info, milestone)?;
+    if !since::check_group_inputs_since_epoch(window_end) {
+        return Err(Error::ReleaseTooEarly);
+    }
+    let objections = Objections::load(0, Source::GroupInput)?;
+    if objections.objected.checked_mul(10).ok_or(Error::Encoding)?
+        >= total_weight
+            .checked_mul(veto_threshold as u128)
+            .ok_or(Error::Encoding)?
+    {
+        return Err(Error::MilestoneVetoed);
+    }
+    Ok(())
+}
+
+// The veto window of the milestone ends the veto epochs after its release epoch
+fn veto_window_end(crowdfunding_info: &CrowdfundingInfo, milestone: u8) -> Result<u64, Error> {
+    milestone
+        .checked_add(1)
+        .and_then(|release| crowdfunding_info.release_epoch(release))
+        .and_then(|epoch| epoch.checked_add(VETO_WINDOW_EPOCHS))
+        .ok_or(Error::Encoding)
+}
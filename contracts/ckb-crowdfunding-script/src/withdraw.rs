@@ -1,24 +1,65 @@
 use super::helper;
 use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{CrowdfundingInfo, Mode, Phase},
+    since,
+};
+use ckb_std::high_level::load_script;
 use core::result::Result;
 
-const CHEQUE_CELL_SINCE: u64 = 0xA000000000000006;
-
-pub fn validate(sender_lock_hash: &[u8; 20], cheque_witness_is_none: bool) -> Result<(), Error> {
-    let cheque_inputs_since = helper::load_group_inputs_since();
-    if cheque_inputs_since
-        .into_iter()
-        .any(|since| since != CHEQUE_CELL_SINCE)
-    {
-        return Err(Error::WithdrawChequeInputSinceError);
+// The backer can take the pledge back while the project is pledging, after it is cancelled or failed,
+// or once the creator has taken all the releases from it.
+// In between, the remaining capacity is only refundable when the creator misses the next release from the pledge,
+// which is due by the releases the pledge has paid, as the creator alone can move the phase of the state cell on.
+// The pledges of the keep-it-all projects are committed, they are refundable while the project is pledging
+// only when the creator misses the startup amount.
+// A finalized position is refunded only by the escrow cells consumed with it.
+pub fn validate(
+    crowdfunding_info: &CrowdfundingInfo,
+    state_type_hash: &[u8; 32],
+) -> Result<(), Error> {
+    let is_udt = crowdfunding_info.currency.is_some();
+    let pledge_info = helper::load_group_pledge_info(is_udt)?;
+    let released = pledge_info.released;
+    let state_info = helper::load_current_state_info(state_type_hash)?;
+    if pledge_info.pledged > 0 {
+        return validate_position(&state_info.phase, is_udt);
     }
-
-    if cheque_witness_is_none {
-        match helper::position_input_by_lock_hash(sender_lock_hash) {
-            Some(position) => helper::check_witness_args(position),
-            None => Err(Error::NoMatchedInputs),
+    match (state_info.phase, crowdfunding_info.mode) {
+        (Phase::Pledging, Mode::AllOrNothing) | (Phase::Failed, _) | (Phase::Cancelled, _) => {
+            Ok(())
+        }
+        // the rest is the rounding dust
+        (Phase::Completed, _) if released == crowdfunding_info.releases() => Ok(()),
+        // the pledge was not counted when the project got funded
+        (phase, _) if phase != Phase::Pledging && phase != Phase::Completed && released == 0 => {
+            Ok(())
+        }
+        (phase, _) => {
+            let failed_epoch = crowdfunding_info
+                .failed_epoch(released)
+                .ok_or(Error::Encoding)?;
+            // Every release takes the same proportion from all the pledges,
+            // so the remaining capacity of the pledge cells is already the pro-rata refund.
+            if since::check_group_inputs_since_epoch(failed_epoch) {
+                Ok(())
+            } else if phase == Phase::Completed {
+                Err(Error::ReleaseNotCollected)
+            } else {
+                Err(Error::ProjectNotFailed)
+            }
         }
-    } else {
-        Ok(())
     }
 }
+
+// The escrow cells refund the position, or the rest of it is the rounding dust once the project has completed.
+// No position is created with it, as only the transfers carry the pledges over.
+fn validate_position(phase: &Phase, is_udt: bool) -> Result<(), Error> {
+    let script = load_script()?;
+    if helper::sum_outputs_positions_pledged(&script, is_udt)? != 0
+        || (phase != &Phase::Completed && !helper::has_escrow_input(&script))
+    {
+        return Err(Error::PositionNotRefunded);
+    }
+    Ok(())
+}
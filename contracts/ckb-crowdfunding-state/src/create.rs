@@ -0,0 +1,46 @@
+use core::result::Result;
+
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{Phase, StateInfo},
+    hash::new_blake2b,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    high_level::{load_cell_type_hash, load_input, load_script_hash, QueryIter},
+};
+
+// The type id makes the state cell the only one of the project,
+// it is the hash of the first input and the index of the state cell in the outputs.
+pub fn validate(type_id: &[u8], state_info: &StateInfo) -> Result<(), Error> {
+    let script_hash = load_script_hash()?;
+    let output_index = QueryIter::new(load_cell_type_hash, Source::Output)
+        .position(|type_hash| type_hash == Some(script_hash))
+        .ok_or(Error::InvalidStateCells)?;
+    let first_input = load_input(0, Source::Input)?;
+
+    let mut hash = [0u8; 32];
+    let mut blake2b = new_blake2b();
+    blake2b.update(first_input.as_slice());
+    blake2b.update(&(output_index as u64).to_le_bytes());
+    blake2b.finalize(&mut hash);
+    if type_id != hash {
+        return Err(Error::InvalidTypeId);
+    }
+
+    let initial_state_info = StateInfo {
+        phase: Phase::Pledging,
+        total_pledged: 0,
+        total_released: 0,
+        total_weight: 0,
+        funding: None,
+        amendments: Vec::new(),
+    };
+    if state_info != &initial_state_info {
+        return Err(Error::InvalidInitialState);
+    }
+    Ok(())
+}
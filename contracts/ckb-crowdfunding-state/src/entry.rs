@@ -0,0 +1,59 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::result::Result;
+
+// Import CKB syscalls and structures
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, prelude::*},
+    debug,
+    error::SysError,
+    high_level::{load_script, look_for_dep_with_data_hash},
+};
+
+use super::create;
+use super::transition;
+use crate::error::Error;
+use ckb_crowdfunding_common::data::{CrowdfundingInfo, StateInfo};
+
+pub fn main() -> Result<(), Error> {
+    let script = load_script()?;
+    let args: Bytes = script.args().unpack();
+    debug!("script args is {:?}", args);
+
+    // args[0..32] is the data hash of the c-cell, args[32..64] is the type id of the state cell,
+    // args[64..84] is the lock hash of the creator (the receiver in the pledge locks)
+    if args.len() != 84 {
+        return Err(Error::InvalidArgument);
+    }
+
+    let index = look_for_dep_with_data_hash(&args[0..32]).map_err(|_| Error::NoCCell)?;
+    let mut crowdfunding_info =
+        CrowdfundingInfo::try_from(index).map_err(|_| Error::CCellInvalidData)?;
+    debug!("crowdfunding_info is {:?}", crowdfunding_info);
+
+    let input_state_info = load_group_state_info(Source::GroupInput)?;
+    let output_state_info = load_group_state_info(Source::GroupOutput)?;
+    match (input_state_info, output_state_info) {
+        (None, Some(output)) => create::validate(&args[32..64], &output),
+        (Some(input), Some(output)) => {
+            crowdfunding_info.amend(&input.amendments);
+            transition::validate(&crowdfunding_info, &args[64..84], &input, &output)
+        }
+        // the pledges refer to the state cell all the time, so it can't be destroyed
+        _ => Err(Error::InvalidStateCells),
+    }
+}
+
+// There is at most one state cell of the project in the inputs and in the outputs
+fn load_group_state_info(source: Source) -> Result<Option<StateInfo>, Error> {
+    let state_info = match StateInfo::try_from(0, source) {
+        Ok(state_info) => state_info,
+        Err(SysError::IndexOutOfBound) => return Ok(None),
+        Err(_) => return Err(Error::StateInvalidData),
+    };
+    match StateInfo::try_from(1, source) {
+        Err(SysError::IndexOutOfBound) => Ok(Some(state_info)),
+        _ => Err(Error::InvalidStateCells),
+    }
+}
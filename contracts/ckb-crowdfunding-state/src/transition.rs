@@ -0,0 +1,300 @@
+use core::result::Result;
+
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{isqrt, udt_amount, CrowdfundingInfo, Mode, Phase, StateInfo},
+    since,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    high_level::{
+        load_cell_capacity, load_cell_data, load_cell_lock, load_cell_lock_hash,
+        load_cell_type_hash, load_input_out_point, load_script, load_script_hash, QueryIter,
+    },
+};
+
+// the args of the pledge lock: the c-cell data hash, the receiver, the sender and the state type hash
+const PLEDGE_ARGS_LEN: usize = 104;
+
+pub fn validate(
+    crowdfunding_info: &CrowdfundingInfo,
+    creator_lock_hash: &[u8],
+    input: &StateInfo,
+    output: &StateInfo,
+) -> Result<(), Error> {
+    if input == output {
+        return Ok(());
+    }
+    check_funding_recorded(input, output)?;
+    if output.amendments != input.amendments {
+        return validate_amendment(crowdfunding_info, input, output);
+    }
+    match output.phase {
+        Phase::Cancelled => validate_cancel(creator_lock_hash, input, output),
+        Phase::Failed => validate_fail(crowdfunding_info, input, output),
+        _ => validate_release(crowdfunding_info, input, output),
+    }
+}
+
+// The funding transaction is recorded from the state cell it created, the funded one without it,
+// by the first transition after it, or by the later ones it has left funded,
+// so a funded state cell without it is always the one created by the funding transaction.
+// The votes count only the positions created by the funding transaction.
+fn check_funding_recorded(input: &StateInfo, output: &StateInfo) -> Result<(), Error> {
+    match (input.funding, output.funding) {
+        (None, None) if output.phase == Phase::Funded && input.phase != Phase::Pledging => {
+            Err(Error::InvalidTransition)
+        }
+        (input_funding, output_funding) if input_funding == output_funding => Ok(()),
+        (None, Some(funding)) if input.phase == Phase::Funded => {
+            if load_input_out_point(0, Source::GroupInput)?
+                .tx_hash()
+                .as_slice()
+                == &funding[..]
+            {
+                Ok(())
+            } else {
+                Err(Error::InvalidTransition)
+            }
+        }
+        _ => Err(Error::InvalidTransition),
+    }
+}
+
+// The creator can cancel the project unless it is over, with an input of the creator lock
+fn validate_cancel(
+    creator_lock_hash: &[u8],
+    input: &StateInfo,
+    output: &StateInfo,
+) -> Result<(), Error> {
+    if !QueryIter::new(load_cell_lock_hash, Source::Input)
+        .any(|lock_hash| &lock_hash[0..20] == creator_lock_hash)
+    {
+        return Err(Error::CreatorNotSigned);
+    }
+    match input.phase {
+        Phase::Pledging | Phase::Funded | Phase::Milestone(_) => check_totals_same(input, output),
+        _ => Err(Error::InvalidTransition),
+    }
+}
+
+// The project fails if it is not funded by the pledge time,
+// or the next milestone is not released in the grace period after its time.
+fn validate_fail(
+    crowdfunding_info: &CrowdfundingInfo,
+    input: &StateInfo,
+    output: &StateInfo,
+) -> Result<(), Error> {
+    let released = input
+        .released(crowdfunding_info.releases())
+        .ok_or(Error::InvalidTransition)?;
+    // the keep-it-all projects don't need the pledge threshold,
+    // they fail only if the creator doesn't take the startup amount in the grace period
+    let failed_epoch = match (input.phase, crowdfunding_info.mode) {
+        (Phase::Pledging, Mode::AllOrNothing) => crowdfunding_info.pledge_time,
+        (Phase::Pledging, Mode::KeepItAll) | (Phase::Funded, _) | (Phase::Milestone(_), _) => {
+            crowdfunding_info
+                .failed_epoch(released)
+                .ok_or(Error::Encoding)?
+        }
+        _ => return Err(Error::InvalidTransition),
+    };
+    if !since::check_group_inputs_since_epoch(failed_epoch) {
+        return Err(Error::TransitionTooEarly);
+    }
+    check_totals_same(input, output)
+}
+
+// The project records the releases one by one, each since its epoch.
+// The first release funds the project with the pledges counted in the transaction,
+// the pledge cells make sure the total pledged and the total weight are right.
+// The locks check the approval of a milestone when it is collected, and the refunds are due
+// by the releases collected from the pledge and escrow cells, never by the phase recorded here.
+fn validate_release(
+    crowdfunding_info: &CrowdfundingInfo,
+    input: &StateInfo,
+    output: &StateInfo,
+) -> Result<(), Error> {
+    let releases = crowdfunding_info.releases();
+    let released = input.released(releases).ok_or(Error::InvalidTransition)?;
+    if crowdfunding_info.vesting && released > 0 {
+        return validate_vesting(crowdfunding_info, input, output);
+    }
+    if released >= releases || output.phase != StateInfo::phase_of(released + 1, releases) {
+        return Err(Error::InvalidTransition);
+    }
+
+    let (epoch, amount) = crowdfunding_info
+        .release(released, output.total_pledged)
+        .ok_or(Error::InvalidTransition)?;
+    if !since::check_group_inputs_since_epoch(epoch) {
+        return Err(Error::TransitionTooEarly);
+    }
+
+    if released == 0 {
+        if crowdfunding_info.mode == Mode::AllOrNothing
+            && output.total_pledged < crowdfunding_info.pledge_threshold
+        {
+            return Err(Error::PledgeThresholdNotReached);
+        }
+        check_funding_pledges(crowdfunding_info, output)?;
+    } else if output.total_pledged != input.total_pledged
+        || output.total_weight != input.total_weight
+    {
+        return Err(Error::InvalidTransition);
+    }
+
+    match input.total_released.checked_add(amount) {
+        Some(total_released)
+            if total_released == output.total_released
+                && total_released <= output.total_pledged =>
+        {
+            Ok(())
+        }
+        _ => Err(Error::ReleasedAmountWrong),
+    }
+}
+
+// The funding counts the pledges of the project in the inputs, the cells locked with the args of the project
+// and of this state cell, and records their total and the quadratic weight of their backers.
+// The state cell can't tell the code of the crowdfunding lock, which counts the same pledges of its own,
+// and a funding with the cells of another lock has no pledges the creator can claim.
+fn check_funding_pledges(
+    crowdfunding_info: &CrowdfundingInfo,
+    output: &StateInfo,
+) -> Result<(), Error> {
+    let data_hash = load_script()?.args().raw_data().slice(0..32);
+    let state_type_hash = load_script_hash()?;
+    let mut backers: Vec<([u8; 20], u128)> = Vec::new();
+    for (index, lock) in QueryIter::new(load_cell_lock, Source::Input).enumerate() {
+        let args = lock.args().raw_data();
+        if args.len() != PLEDGE_ARGS_LEN
+            || args[0..32] != data_hash[..]
+            || args[72..104] != state_type_hash[..]
+        {
+            continue;
+        }
+        let amount = match &crowdfunding_info.currency {
+            None => load_cell_capacity(index, Source::Input)? as u128,
+            Some(udt_type_hash) => {
+                if load_cell_type_hash(index, Source::Input)?.as_ref() != Some(udt_type_hash) {
+                    continue;
+                }
+                udt_amount(&load_cell_data(index, Source::Input)?).ok_or(Error::Encoding)?
+            }
+        };
+        let mut backer = [0u8; 20];
+        backer.copy_from_slice(&args[52..72]);
+        match backers.iter_mut().find(|(other, _)| other == &backer) {
+            Some((_, sum)) => *sum = sum.checked_add(amount).ok_or(Error::Encoding)?,
+            None => backers.push((backer, amount)),
+        }
+    }
+
+    let total_pledged = backers
+        .iter()
+        .try_fold(0u128, |sum, (_, amount)| sum.checked_add(*amount))
+        .ok_or(Error::Encoding)?;
+    if total_pledged != output.total_pledged {
+        return Err(Error::TotalPledgedWrong);
+    }
+    let total_weight = if crowdfunding_info.quadratic {
+        backers
+            .iter()
+            .try_fold(0u128, |sum, (_, amount)| sum.checked_add(isqrt(*amount)))
+            .ok_or(Error::Encoding)?
+    } else {
+        0
+    };
+    if total_weight != output.total_weight {
+        return Err(Error::TotalWeightWrong);
+    }
+    Ok(())
+}
+
+// The vesting projects stream the milestones after the startup amount,
+// the creator can record any amount vested by the epoch of the since, and the phase follows the releases completed.
+fn validate_vesting(
+    crowdfunding_info: &CrowdfundingInfo,
+    input: &StateInfo,
+    output: &StateInfo,
+) -> Result<(), Error> {
+    if output.total_pledged != input.total_pledged || output.total_weight != input.total_weight {
+        return Err(Error::InvalidTransition);
+    }
+    let epoch = since::group_inputs_since_epoch().ok_or(Error::TransitionTooEarly)?;
+    let vested_amount = crowdfunding_info
+        .vested_amount(epoch, output.total_pledged)
+        .ok_or(Error::Encoding)?;
+    if output.total_released <= input.total_released
+        || output.total_released > vested_amount
+        || output.total_released > output.total_pledged
+    {
+        return Err(Error::ReleasedAmountWrong);
+    }
+    let completed = crowdfunding_info
+        .completed_releases(output.total_released, output.total_pledged)
+        .ok_or(Error::Encoding)?;
+    if output.phase != StateInfo::phase_of(completed, crowdfunding_info.releases()) {
+        return Err(Error::InvalidTransition);
+    }
+    Ok(())
+}
+
+// The creator moves the next milestone to a later time, before the milestone after it,
+// and the proposal cell in the inputs makes sure the backers have approved the new time.
+// The lock hash of the proposal is recorded with the amendment,
+// as the state cell can't tell the crowdfunding lock, the lock takes only the amendments of its own proposals.
+fn validate_amendment(
+    crowdfunding_info: &CrowdfundingInfo,
+    input: &StateInfo,
+    output: &StateInfo,
+) -> Result<(), Error> {
+    let amendment = match output.amendments.split_last() {
+        Some((amendment, amendments)) if amendments == &input.amendments[..] => amendment,
+        _ => return Err(Error::InvalidAmendment),
+    };
+    if output.phase != input.phase {
+        return Err(Error::InvalidTransition);
+    }
+    check_totals_same(input, output)?;
+    let releases = crowdfunding_info.releases();
+    let released = input.released(releases).ok_or(Error::InvalidTransition)?;
+    if crowdfunding_info.vesting
+        || released == 0
+        || released >= releases
+        || amendment.milestone != released - 1
+    {
+        return Err(Error::InvalidAmendment);
+    }
+    let epoch = crowdfunding_info
+        .release_epoch(released)
+        .ok_or(Error::Encoding)?;
+    if amendment.time <= epoch
+        || crowdfunding_info
+            .release_epoch(released + 1)
+            .map_or(false, |next_epoch| amendment.time >= next_epoch)
+    {
+        return Err(Error::InvalidAmendment);
+    }
+    if !QueryIter::new(load_cell_lock_hash, Source::Input)
+        .any(|lock_hash| lock_hash == amendment.proposal)
+    {
+        return Err(Error::InvalidAmendment);
+    }
+    Ok(())
+}
+
+fn check_totals_same(input: &StateInfo, output: &StateInfo) -> Result<(), Error> {
+    if input.total_pledged == output.total_pledged
+        && input.total_released == output.total_released
+        && input.total_weight == output.total_weight
+    {
+        Ok(())
+    } else {
+        Err(Error::InvalidTransition)
+    }
+}
@@ -0,0 +1,80 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::result::Result;
+
+use alloc::vec::Vec;
+
+// Import CKB syscalls and structures
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, prelude::*},
+    debug,
+    high_level::{load_cell_data, load_cell_lock_hash, load_script, QueryIter},
+};
+
+use crate::error::Error;
+use crate::reveal;
+use crate::tally;
+use ckb_crowdfunding_common::data::{
+    CommitmentInfo, DelegationInfo, VoteInfo, COMMITMENT_LEN, DELEGATION_LEN, TALLY_LEN,
+};
+
+pub fn main() -> Result<(), Error> {
+    let script = load_script()?;
+    let args: Bytes = script.args().unpack();
+    debug!("script args is {:?}", args);
+
+    // args[0..32] is the data hash of the c-cell (project id),
+    // args[32..64] and args[64] are the code hash and the hash type of the crowdfunding lock,
+    // so the votes of a project never count for another project or another crowdfunding lock
+    if args.len() != 65 {
+        return Err(Error::InvalidArgument);
+    }
+
+    // The votes on the milestones or on their amendments, the commitments and the delegations
+    // are created or changed only by the backers, who unlock a cell of their own lock in the inputs,
+    // and a backer commits to or reveals the vote on a milestone once in a transaction,
+    // while the lock rejects the votes revealed twice from the commitments of different transactions.
+    // The cells consumed are left to their locks, so a backer revokes a delegation by consuming its cell,
+    // the tally cells are checked in `tally.rs` and the votes revealed from the commitments in `reveal.rs`.
+    let mut committed: Vec<(u8, [u8; 20])> = Vec::new();
+    for (index, data) in QueryIter::new(load_cell_data, Source::GroupOutput).enumerate() {
+        let backer = match data.len() {
+            TALLY_LEN => {
+                tally::validate(index, &args, &data)?;
+                continue;
+            }
+            DELEGATION_LEN => {
+                DelegationInfo::from_slice(&data)
+                    .map_err(|_| Error::VoteInvalidData)?
+                    .delegator
+            }
+            COMMITMENT_LEN => {
+                let commitment =
+                    CommitmentInfo::from_slice(&data).map_err(|_| Error::VoteInvalidData)?;
+                if committed.contains(&(commitment.milestone, commitment.backer)) {
+                    return Err(Error::CommitmentDuplicated);
+                }
+                committed.push((commitment.milestone, commitment.backer));
+                commitment.backer
+            }
+            _ => {
+                let vote = VoteInfo::from_slice(&data).map_err(|_| Error::VoteInvalidData)?;
+                if let Some(commitment) = &vote.commitment {
+                    if committed.contains(&(vote.milestone, vote.backer)) {
+                        return Err(Error::CommitmentDuplicated);
+                    }
+                    committed.push((vote.milestone, vote.backer));
+                    reveal::validate(index, &args, &vote, commitment)?;
+                }
+                vote.backer
+            }
+        };
+        if !QueryIter::new(load_cell_lock_hash, Source::Input)
+            .any(|lock_hash| lock_hash[0..20] == backer)
+        {
+            return Err(Error::VoteNotAuthorized);
+        }
+    }
+    Ok(())
+}
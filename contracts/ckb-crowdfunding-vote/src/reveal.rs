@@ -0,0 +1,73 @@
+use core::result::Result;
+
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    high_level::{
+        load_cell_data, load_header, load_witness_args, look_for_dep_with_data_hash, QueryIter,
+    },
+};
+
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{CommitmentInfo, CrowdfundingInfo, VoteInfo},
+    since,
+};
+
+// The vote is revealed from the commitment of the backer consumed in the transaction,
+// with the salt in the output_type of the witness of the vote.
+// The commitment must be made before the time of the milestone, proven by the header of its block
+// in the header deps, and revealed since then, so no vote on the milestone is seen before the commitments are made.
+// The lock counts only the votes revealed within the reveal window after the time of the milestone.
+pub fn validate(
+    index: usize,
+    args: &[u8],
+    vote: &VoteInfo,
+    commitment: &[u8; 32],
+) -> Result<(), Error> {
+    let salt = load_witness_args(index, Source::GroupOutput)?
+        .output_type()
+        .to_opt()
+        .map(|salt| salt.raw_data())
+        .unwrap_or_default();
+    if salt.len() != 32
+        || &CommitmentInfo::commitment_of(
+            &args[0..32],
+            vote.milestone,
+            &vote.backer,
+            vote.approve,
+            &salt,
+        ) != commitment
+    {
+        return Err(Error::RevealNotMatched);
+    }
+    let input_index = QueryIter::new(load_cell_data, Source::GroupInput)
+        .position(|data| {
+            CommitmentInfo::from_slice(&data).map_or(false, |input| {
+                input.milestone == vote.milestone
+                    && input.backer == vote.backer
+                    && &input.commitment == commitment
+            })
+        })
+        .ok_or(Error::RevealNotMatched)?;
+
+    let index = look_for_dep_with_data_hash(&args[0..32]).map_err(|_| Error::NoCCell)?;
+    let crowdfunding_info =
+        CrowdfundingInfo::try_from(index).map_err(|_| Error::CCellInvalidData)?;
+    let deadline = crowdfunding_info
+        .milestones
+        .get(vote.milestone as usize)
+        .ok_or(Error::VoteInvalidData)?
+        .time;
+    let committed_epoch: u64 = load_header(input_index, Source::GroupInput)?
+        .raw()
+        .epoch()
+        .unpack();
+    if since::epoch_number(committed_epoch) >= deadline {
+        return Err(Error::CommitmentTooLate);
+    }
+    if !since::check_group_inputs_since_epoch(deadline) {
+        return Err(Error::RevealTooEarly);
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use core::result::Result;
+
+use alloc::vec::Vec;
+
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{packed::Script, prelude::*},
+    high_level::{
+        load_cell_data, load_cell_lock, load_cell_lock_hash, load_cell_type_hash, load_input_since,
+        load_script_hash, load_witness_args, look_for_dep_with_data_hash, QueryIter,
+    },
+};
+
+use crate::error::Error;
+use ckb_crowdfunding_common::{
+    data::{
+        CrowdfundingInfo, DelegationInfo, TallyInfo, TallyLeaf, DELEGATION_LEN,
+        TALLY_CHALLENGE_EPOCHS, TALLY_LEAF_LEN,
+    },
+    merkle, position, since,
+};
+
+// the args of the tally lock: the args of the pledge lock without a sender, the milestone index and 0
+const TALLY_ARGS_LEN: usize = 106;
+
+// The tally cell of a milestone is locked by the crowdfunding lock of the project, which lets anyone challenge it.
+// It is submitted with all its leaves in the output_type of the witness, ordered by the backers without duplicates
+// and each pointing to the next backer, so a vote left out of the tree can be proven by its neighbour,
+// and every leaf must weigh no more than the position of its backer created by the funding transaction
+// (their integer square root in the quadratic voting) and the backers delegating to it without leaves of their own,
+// so only the signatures of the backers are left to the challenges.
+// It is confirmed after the challenge epochs since it was submitted.
+pub fn validate(index: usize, args: &[u8], data: &[u8]) -> Result<(), Error> {
+    let tally_info = TallyInfo::from_slice(data).map_err(|_| Error::TallyInvalidData)?;
+    let lock = load_cell_lock(index, Source::GroupOutput)?;
+    let lock_args = lock.args().raw_data();
+    if lock.code_hash().as_slice() != &args[32..64]
+        || lock.hash_type().as_slice() != &args[64..65]
+        || lock_args.len() != TALLY_ARGS_LEN
+        || lock_args[0..32] != args[0..32]
+        || lock_args[52..72] != [0u8; 20]
+        || lock_args[105] != 0
+    {
+        return Err(Error::TallyLockWrong);
+    }
+
+    if tally_info.confirmed {
+        return check_challenge_over(
+            &load_cell_lock_hash(index, Source::GroupOutput)?,
+            &tally_info,
+        );
+    }
+
+    let leaves = load_witness_args(index, Source::GroupOutput)?
+        .output_type()
+        .to_opt()
+        .map(|leaves| leaves.raw_data())
+        .unwrap_or_default();
+    if leaves.is_empty() || leaves.len() % TALLY_LEAF_LEN != 0 {
+        return Err(Error::TallyLeavesWrong);
+    }
+    let leaves = leaves
+        .chunks_exact(TALLY_LEAF_LEN)
+        .map(TallyLeaf::from_slice)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Error::TallyLeavesWrong)?;
+    if leaves
+        .windows(2)
+        .any(|pair| pair[0].backer >= pair[1].backer || pair[0].next != pair[1].backer)
+        || leaves.last().map(|leaf| leaf.next) != Some([0u8; 20])
+    {
+        return Err(Error::TallyLeavesWrong);
+    }
+    let root = merkle::sum_root(leaves.iter().map(TallyLeaf::node).collect());
+    if root != Some(tally_info.root()) {
+        return Err(Error::TallyLeavesWrong);
+    }
+
+    let weights = load_vote_weights(&lock)?;
+    let weight_of = |backer: &[u8; 20]| position::weight_of(&weights, backer);
+    let delegations = load_delegations()?;
+    for leaf in &leaves {
+        let mut weight = weight_of(&leaf.backer);
+        // the delegated votes count for the delegate unless the delegator has voted directly
+        for delegation in delegations
+            .iter()
+            .filter(|delegation| delegation.delegate == leaf.backer)
+        {
+            if leaves
+                .binary_search_by(|other| other.backer.cmp(&delegation.delegator))
+                .is_err()
+            {
+                weight = weight
+                    .checked_add(weight_of(&delegation.delegator))
+                    .ok_or(Error::Encoding)?;
+            }
+        }
+        if leaf.weight > weight {
+            return Err(Error::TallyWeightWrong);
+        }
+    }
+    Ok(())
+}
+
+// The delegations of the project in the cell deps, each delegator referenced once.
+// They are never followed past the delegate, so the verification is linear in the cell deps.
+fn load_delegations() -> Result<Vec<DelegationInfo>, Error> {
+    let script_hash = load_script_hash()?;
+    let mut delegations: Vec<DelegationInfo> = Vec::new();
+    for (index, type_hash) in QueryIter::new(load_cell_type_hash, Source::CellDep).enumerate() {
+        if type_hash != Some(script_hash) {
+            continue;
+        }
+        let data = load_cell_data(index, Source::CellDep)?;
+        if data.len() != DELEGATION_LEN {
+            continue;
+        }
+        let delegation = DelegationInfo::from_slice(&data).map_err(|_| Error::VoteInvalidData)?;
+        if delegations
+            .iter()
+            .any(|other| other.delegator == delegation.delegator)
+        {
+            return Err(Error::DelegationDuplicated);
+        }
+        delegations.push(delegation);
+    }
+    Ok(delegations)
+}
+
+// The tally is confirmed from the same tally submitted at least the challenge epochs ago
+fn check_challenge_over(lock_hash: &[u8; 32], tally_info: &TallyInfo) -> Result<(), Error> {
+    for (index, data) in QueryIter::new(load_cell_data, Source::GroupInput).enumerate() {
+        let input_tally_info = match TallyInfo::from_slice(&data) {
+            Ok(input_tally_info) => input_tally_info,
+            Err(_) => continue,
+        };
+        if &load_cell_lock_hash(index, Source::GroupInput)? == lock_hash
+            && input_tally_info.root() == tally_info.root()
+            && (input_tally_info.confirmed
+                || since::is_relative_epoch_since_of(
+                    load_input_since(index, Source::GroupInput)?,
+                    TALLY_CHALLENGE_EPOCHS,
+                ))
+        {
+            return Ok(());
+        }
+    }
+    Err(Error::TallyNotConfirmable)
+}
+
+// The vote weights of the backers from their positions created by the funding transaction
+// in the inputs and the cell deps, ordered by the backers
+fn load_vote_weights(tally_lock: &Script) -> Result<Vec<([u8; 20], u128)>, Error> {
+    let tally_args = tally_lock.args().raw_data();
+    let index = look_for_dep_with_data_hash(&tally_args[0..32]).map_err(|_| Error::NoCCell)?;
+    let crowdfunding_info =
+        CrowdfundingInfo::try_from(index).map_err(|_| Error::CCellInvalidData)?;
+    let funding_tx_hash =
+        position::load_funding_tx_hash(&tally_args[72..104])?.ok_or(Error::NoStateCell)?;
+    let weights = position::load_position_weights(
+        |lock| {
+            let lock_args = lock.args().raw_data();
+            lock.code_hash().as_slice() == tally_lock.code_hash().as_slice()
+                && lock.hash_type().as_slice() == tally_lock.hash_type().as_slice()
+                && lock_args.len() == 104
+                && lock_args[0..52] == tally_args[0..52]
+                && lock_args[72..104] == tally_args[72..104]
+        },
+        &funding_tx_hash,
+        crowdfunding_info.currency.is_some(),
+    )?;
+    Ok(weights
+        .into_iter()
+        .map(|(backer, weight)| (backer, crowdfunding_info.vote_weight(weight)))
+        .collect())
+}
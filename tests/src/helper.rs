@@ -34,8 +34,12 @@ pub fn sign_tx(tx: TransactionView, key: &Privkey) -> TransactionView {
     let mut blake2b = new_blake2b();
     let mut message = [0u8; 32];
     blake2b.update(&tx_hash.raw_data());
-    // digest the first witness
-    let witness = packed::WitnessArgs::default();
+    // digest the first witness, keep its input_type and output_type if there are
+    let witness = tx
+        .witnesses()
+        .get(0)
+        .and_then(|witness| packed::WitnessArgs::from_slice(&witness.raw_data()).ok())
+        .unwrap_or_default();
     let zero_lock: Bytes = {
         let mut buf = Vec::new();
         buf.resize(SIGNATURE_SIZE, 0);
@@ -49,6 +53,12 @@ pub fn sign_tx(tx: TransactionView, key: &Privkey) -> TransactionView {
     let witness_len = witness_for_digest.as_bytes().len() as u64;
     blake2b.update(&witness_len.to_le_bytes());
     blake2b.update(&witness_for_digest.as_bytes());
+    // digest the witnesses not covered by the inputs
+    for i in tx.inputs().len()..witnesses_len {
+        let witness = tx.witnesses().get(i).unwrap().raw_data();
+        blake2b.update(&(witness.len() as u64).to_le_bytes());
+        blake2b.update(&witness);
+    }
     blake2b.finalize(&mut message);
     let message = H256::from(message);
     let sig = key.sign_recoverable(&message).expect("sign");
@@ -7,13 +7,127 @@ use ckb_testtool::builtin::ALWAYS_SUCCESS;
 use ckb_testtool::ckb_crypto::secp::Privkey;
 use ckb_testtool::ckb_error::Error;
 use ckb_testtool::ckb_hash::blake2b_256;
-use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionBuilder, packed::*, prelude::*};
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{EpochNumberWithFraction, HeaderBuilder, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+    H256,
+};
 use ckb_testtool::context::Context;
 
 const MAX_CYCLES: u64 = 10_000_000;
+const SHANNONS_PER_CKB: u64 = 100_000_000;
+const SINCE_ABSOLUTE_EPOCH_FLAG: u64 = 0x2000_0000_0000_0000;
+const SINCE_RELATIVE_EPOCH_FLAG: u64 = 0xA000_0000_0000_0000;
 
 // error numbers
 const ERROR_EMPTY_ARGS: i8 = 5;
+const ERROR_WRONG_PUB_KEY: i8 = 9;
+const ERROR_CCELL_INVALID_DATA: i8 = 15;
+const ERROR_RELEASE_TOO_EARLY: i8 = 18;
+const ERROR_RELEASE_CAPACITY_WRONG: i8 = 21;
+const ERROR_PROJECT_NOT_FAILED: i8 = 22;
+const ERROR_PROJECT_CANCELLED: i8 = 23;
+const ERROR_INVALID_ACTION: i8 = 25;
+const ERROR_RELEASE_NOT_COLLECTED: i8 = 30;
+const ERROR_TRANSFER_OUTPUTS_WRONG: i8 = 31;
+const ERROR_TRANSFER_CAPACITY_WRONG: i8 = 32;
+const ERROR_PLEDGE_CAPACITY_NOT_CONSERVED: i8 = 33;
+const ERROR_HARD_CAP_EXCEEDED: i8 = 34;
+const ERROR_PLEDGE_ABOVE_MAXIMUM: i8 = 36;
+const ERROR_BACKER_NOT_ALLOWED: i8 = 37;
+const ERROR_BACKER_NOT_ATTESTED: i8 = 38;
+const ERROR_ATTESTATION_EXPIRED: i8 = 39;
+const ERROR_PLATFORM_FEE_WRONG: i8 = 40;
+const ERROR_PAYEE_AMOUNT_WRONG: i8 = 41;
+const ERROR_ESCROW_OUTPUTS_WRONG: i8 = 42;
+const ERROR_NOTHING_TO_REFUND: i8 = 44;
+const ERROR_MILESTONE_NOT_APPROVED: i8 = 46;
+const ERROR_TALLY_NOT_FRAUDULENT: i8 = 49;
+const ERROR_MILESTONE_QUORUM_NOT_REACHED: i8 = 50;
+const ERROR_MILESTONE_VETOED: i8 = 51;
+const ERROR_OBJECTIONS_WRONG: i8 = 53;
+const ERROR_COUNCIL_SIGNATURES_WRONG: i8 = 54;
+const ERROR_MILESTONE_NOT_APPROVED_BY_COUNCIL: i8 = 55;
+const ERROR_ARBITRATOR_NOT_SIGNED: i8 = 56;
+const ERROR_TOTAL_WEIGHT_WRONG: i8 = 57;
+const ERROR_AMENDMENT_WRONG: i8 = 58;
+const ERROR_MILESTONE_APPROVED: i8 = 59;
+const ERROR_COMMITMENT_DUPLICATED: i8 = 60;
+const ERROR_DELEGATION_DUPLICATED: i8 = 61;
+const ERROR_OBJECTION_TOO_LATE: i8 = 62;
+const ERROR_POSITION_NOT_REFUNDED: i8 = 63;
+// error numbers of the state type script
+const ERROR_STATE_INVALID_TRANSITION: i8 = 12;
+const ERROR_STATE_TRANSITION_TOO_EARLY: i8 = 13;
+const ERROR_STATE_PLEDGE_THRESHOLD_NOT_REACHED: i8 = 14;
+const ERROR_STATE_RELEASED_AMOUNT_WRONG: i8 = 15;
+const ERROR_STATE_INVALID_AMENDMENT: i8 = 16;
+const ERROR_STATE_TOTAL_PLEDGED_WRONG: i8 = 17;
+const ERROR_STATE_CREATOR_NOT_SIGNED: i8 = 19;
+// error numbers of the receipt type script
+const ERROR_RECEIPT_MINT_AMOUNT_WRONG: i8 = 7;
+const ERROR_RECEIPT_PLEDGE_BELOW_MINIMUM: i8 = 12;
+const ERROR_RECEIPT_TRANSFER_AMOUNT_WRONG: i8 = 14;
+const ERROR_RECEIPT_BURN_AMOUNT_WRONG: i8 = 15;
+// errors of the vote type script
+const ERROR_VOTE_INVALID_DATA: i8 = 6;
+const ERROR_VOTE_NOT_AUTHORIZED: i8 = 7;
+const ERROR_VOTE_TALLY_WEIGHT_WRONG: i8 = 11;
+const ERROR_VOTE_TALLY_NOT_CONFIRMABLE: i8 = 12;
+const ERROR_VOTE_REVEAL_NOT_MATCHED: i8 = 16;
+const ERROR_VOTE_REVEAL_TOO_EARLY: i8 = 17;
+const ERROR_VOTE_COMMITMENT_TOO_LATE: i8 = 18;
+const ERROR_VOTE_COMMITMENT_DUPLICATED: i8 = 19;
+const ERROR_VOTE_DELEGATION_DUPLICATED: i8 = 20;
+
+// actions
+const ACTION_DEFAULT: u8 = 0;
+const ACTION_TRANSFER: u8 = 1;
+const ACTION_FINALIZE: u8 = 2;
+const ACTION_CHALLENGE: u8 = 3;
+const ACTION_OBJECT: u8 = 4;
+const ACTION_DISPUTE: u8 = 5;
+const ACTION_INVALID: u8 = 0xff;
+
+// decisions of the arbitrator
+const DECISION_RELEASE: u8 = 0;
+const DECISION_REFUND: u8 = 1;
+
+// kinds of the tally challenges
+const CHALLENGE_WRONG_SIGNATURE: u8 = 0;
+const CHALLENGE_VOTE_LEFT_OUT: u8 = 1;
+const CHALLENGE_WEIGHT_TOO_HIGH: u8 = 2;
+
+// fields of the project data
+const FIELD_MODE: u8 = 2;
+const FIELD_HARD_CAP: u8 = 3;
+const FIELD_MIN_PLEDGE: u8 = 4;
+const FIELD_MAX_PLEDGE: u8 = 5;
+const FIELD_ALLOW_LIST: u8 = 6;
+const FIELD_KYC_ISSUER: u8 = 7;
+const FIELD_PLATFORM_FEE: u8 = 8;
+const FIELD_PAYEES: u8 = 9;
+const FIELD_VESTING: u8 = 10;
+const FIELD_VOTE_TYPE: u8 = 11;
+const FIELD_QUORUMS: u8 = 12;
+const FIELD_VETO_THRESHOLD: u8 = 13;
+const FIELD_COUNCIL: u8 = 14;
+const FIELD_ARBITRATOR: u8 = 15;
+const FIELD_QUADRATIC: u8 = 16;
+const FIELD_COMMIT_REVEAL: u8 = 17;
+
+// modes of the project
+const MODE_ALL_OR_NOTHING: u8 = 0;
+const MODE_KEEP_IT_ALL: u8 = 1;
+
+// phases of the state cell
+const PHASE_PLEDGING: u8 = 0;
+const PHASE_FUNDED: u8 = 1;
+const PHASE_MILESTONE: u8 = 3;
+const PHASE_COMPLETED: u8 = 4;
+const PHASE_CANCELLED: u8 = 5;
 
 fn assert_script_error(err: Error, err_code: i8) {
     let error_string = err.to_string();
@@ -25,7 +139,49 @@ fn assert_script_error(err: Error, err_code: i8) {
     );
 }
 
+// pledge time 10, pledge threshold 200, startup amount 100, milestones: (20, 50, 6), (30, 50, 6)
+const PROJECT_DATA: [u8; 42] = [
+    0, 0, 0, 0, 0, 0, 0, 10, 0, 0, 0, 200, 0, 0, 0, 100, 0, 0, 0, 0, 0, 0, 0, 20, 0, 0, 0, 50, 6,
+    0, 0, 0, 0, 0, 0, 0, 30, 0, 0, 0, 50, 6,
+];
+
+// the project data of the version 1 with the amounts in shannons,
+// pledge time 10, pledge threshold 200 CKB, startup amount 100 CKB and 50 shannons, milestones: (20, 50 CKB, 6)
+fn build_project_data_v1() -> Vec<u8> {
+    let mut data = vec![1];
+    data.extend_from_slice(&10u64.to_be_bytes());
+    data.extend_from_slice(&(200 * SHANNONS_PER_CKB as u128).to_be_bytes());
+    data.extend_from_slice(&(100 * SHANNONS_PER_CKB as u128 + 50).to_be_bytes());
+    data.extend_from_slice(&20u64.to_be_bytes());
+    data.extend_from_slice(&(50 * SHANNONS_PER_CKB as u128).to_be_bytes());
+    data.push(6);
+    data
+}
+
+// the project data of the version 2 with the fields,
+// pledge time 10, pledge threshold 200 CKB, startup amount 100 CKB, milestones: (20, 50 CKB, 6)
+fn build_project_data_v2(fields: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let mut data = vec![2];
+    data.extend_from_slice(&10u64.to_be_bytes());
+    data.extend_from_slice(&(200 * SHANNONS_PER_CKB as u128).to_be_bytes());
+    data.extend_from_slice(&(100 * SHANNONS_PER_CKB as u128).to_be_bytes());
+    data.push(1);
+    data.extend_from_slice(&20u64.to_be_bytes());
+    data.extend_from_slice(&(50 * SHANNONS_PER_CKB as u128).to_be_bytes());
+    data.push(6);
+    for (field, value) in fields {
+        data.push(*field);
+        data.push(value.len() as u8);
+        data.extend_from_slice(value);
+    }
+    data
+}
+
 fn prepare_c_cell(context: &mut Context) -> (CellDep, [u8; 32]) {
+    prepare_c_cell_with_data(context, &PROJECT_DATA)
+}
+
+fn prepare_c_cell_with_data(context: &mut Context, data: &[u8]) -> (CellDep, [u8; 32]) {
     let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
 
     let contract_bin_2: Bytes = Loader::default().load_binary("ckb-project-type-id");
@@ -35,10 +191,6 @@ fn prepare_c_cell(context: &mut Context) -> (CellDep, [u8; 32]) {
     let lock_script_for_c_cell = context
         .build_script(&always_success_out_point, Bytes::from(vec![42]))
         .expect("lock_script_for_c_cell");
-    let data = [
-        0, 0, 0, 0, 0, 0, 0, 10, 0, 0, 0, 200, 0, 0, 0, 100, 0, 0, 0, 0, 0, 0, 0, 20, 0, 0, 0, 50,
-        6, 0, 0, 0, 0, 0, 0, 0, 30, 0, 0, 0, 50, 6,
-    ];
     let data_hash = blake2b_256(data);
     let type_script = context.build_script(&out_point_2, Bytes::from(data_hash.to_vec()));
     let type_script = type_script.pack();
@@ -47,7 +199,7 @@ fn prepare_c_cell(context: &mut Context) -> (CellDep, [u8; 32]) {
         .lock(lock_script_for_c_cell.clone())
         .type_(type_script)
         .build();
-    let c_cell = context.create_cell(c_cell, Bytes::copy_from_slice(&data));
+    let c_cell = context.create_cell(c_cell, Bytes::copy_from_slice(data));
     let c_cell_dep = CellDep::new_builder().out_point(c_cell).build();
     (c_cell_dep, data_hash)
 }
@@ -73,6 +225,230 @@ fn prepare_sender_key() -> (String, String, [u8; 20]) {
         [152, 130, 119, 177, 187, 68, 13, 158, 215, 120, 60, 184, 77, 93, 71, 97, 70, 85, 100, 155])
 }
 
+fn absolute_epoch_since(epoch: u64) -> u64 {
+    SINCE_ABSOLUTE_EPOCH_FLAG | EpochNumberWithFraction::new(epoch, 0, 1).full_value()
+}
+
+fn relative_epoch_since(epochs: u64) -> u64 {
+    SINCE_RELATIVE_EPOCH_FLAG | EpochNumberWithFraction::new(epochs, 0, 1).full_value()
+}
+
+fn prepare_creator_lock_script(context: &mut Context) -> Script {
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    context
+        .build_script(&always_success_out_point, Bytes::from(vec![0]))
+        .expect("creator lock script")
+}
+
+// a type id of the state cell which has been created
+const STATE_TYPE_ID: [u8; 32] = [7; 32];
+
+fn prepare_state_type_script(
+    context: &mut Context,
+    data_hash: &[u8; 32],
+    type_id: &[u8],
+) -> Script {
+    let contract_bin: Bytes = Loader::default().load_binary("ckb-crowdfunding-state");
+    let out_point = context.deploy_cell(contract_bin);
+    let creator_lock_hash = prepare_creator_lock_script(context).calc_script_hash();
+    let mut args = data_hash.to_vec();
+    args.extend_from_slice(type_id);
+    args.extend_from_slice(&creator_lock_hash.raw_data()[0..20]);
+    context
+        .build_script(&out_point, Bytes::from(args))
+        .expect("state type script")
+}
+
+// phase 1 byte, milestone index 1 byte, total_pledged 16 bytes, total_released 16 bytes
+fn build_state(phase: u8, index: u8, total_pledged: u64, total_released: u64) -> Bytes {
+    build_state_of_weight(phase, index, total_pledged, total_released, None)
+}
+
+// the total weight 16 bytes follows in the quadratic voting
+fn build_state_of_weight(
+    phase: u8,
+    index: u8,
+    total_pledged: u64,
+    total_released: u64,
+    total_weight: Option<u64>,
+) -> Bytes {
+    let mut data = vec![phase, index];
+    data.extend_from_slice(&(total_pledged as u128).to_be_bytes());
+    data.extend_from_slice(&(total_released as u128).to_be_bytes());
+    if let Some(total_weight) = total_weight {
+        data.extend_from_slice(&(total_weight as u128).to_be_bytes());
+    }
+    Bytes::from(data)
+}
+
+// the funding transaction of the project, which creates the state cell funded and the positions of the backers
+const FUNDING_TX_HASH: [u8; 32] = [0xf0; 32];
+
+// the state recording the funding transaction after the total weight
+fn build_funded_state(
+    phase: u8,
+    index: u8,
+    total_pledged: u64,
+    total_released: u64,
+    total_weight: Option<u64>,
+) -> Bytes {
+    let mut data = build_state_of_weight(
+        phase,
+        index,
+        total_pledged,
+        total_released,
+        Some(total_weight.unwrap_or(0)),
+    )
+    .to_vec();
+    data.extend_from_slice(&FUNDING_TX_HASH);
+    Bytes::from(data)
+}
+
+// a cell created by the funding transaction
+fn create_funding_cell(context: &mut Context, cell: CellOutput, data: Bytes) -> OutPoint {
+    let out_point = OutPoint::new(FUNDING_TX_HASH.pack(), context.cells.len() as u32);
+    context.create_cell_with_out_point(out_point.clone(), cell, data);
+    out_point
+}
+
+fn build_state_output(context: &mut Context, type_script: &Script) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity((200 * SHANNONS_PER_CKB).pack())
+        .lock(prepare_creator_lock_script(context))
+        .type_(Some(type_script.clone()).pack())
+        .build()
+}
+
+fn build_state_input(
+    context: &mut Context,
+    type_script: &Script,
+    data: Bytes,
+    since: u64,
+) -> CellInput {
+    let cell = build_state_output(context, type_script);
+    let input_out_point = create_funding_cell(context, cell, data);
+    CellInput::new_builder()
+        .previous_output(input_out_point)
+        .since(since.pack())
+        .build()
+}
+
+fn build_state_dep(context: &mut Context, type_script: &Script, data: Bytes) -> CellDep {
+    let cell = build_state_output(context, type_script);
+    let out_point = create_funding_cell(context, cell, data);
+    CellDep::new_builder().out_point(out_point).build()
+}
+
+// the receipts of the pledges locked by the crowdfunding lock of the project
+fn prepare_receipt_type_script(context: &mut Context, lock_script: &Script) -> Script {
+    let contract_bin: Bytes = Loader::default().load_binary("ckb-crowdfunding-receipt");
+    let out_point = context.deploy_cell(contract_bin);
+    let mut args = lock_script.args().raw_data()[0..32].to_vec();
+    args.extend_from_slice(lock_script.code_hash().as_slice());
+    args.extend_from_slice(lock_script.hash_type().as_slice());
+    context
+        .build_script(&out_point, Bytes::from(args))
+        .expect("receipt type script")
+}
+
+// backer 20 bytes, amount 16 bytes
+fn build_receipt(backer: &[u8; 20], amount: u64) -> Bytes {
+    let mut data = backer.to_vec();
+    data.extend_from_slice(&(amount as u128).to_be_bytes());
+    Bytes::from(data)
+}
+
+// deploy the crowdfunding lock of the test project and backer, return it with the state type script and the cell deps
+fn prepare_lock_script(context: &mut Context) -> (Script, Script, Vec<CellDep>) {
+    prepare_lock_script_of_project(context, &PROJECT_DATA)
+}
+
+fn prepare_lock_script_of_project(
+    context: &mut Context,
+    project_data: &[u8],
+) -> (Script, Script, Vec<CellDep>) {
+    let (c_cell_dep, data_hash) = prepare_c_cell_with_data(context, project_data);
+    let state_type_script = prepare_state_type_script(context, &data_hash, &STATE_TYPE_ID);
+
+    let contract_bin: Bytes = Loader::default().load_binary("ckb-crowdfunding-script");
+    let out_point = context.deploy_cell(contract_bin);
+
+    let (_, _, receiver_lock_hash_h160) = parepare_receiver_key();
+    let (_, _, sender_lock_hash_h160) = prepare_sender_key();
+    let mut args = data_hash.to_vec();
+    args.extend_from_slice(&receiver_lock_hash_h160);
+    args.extend_from_slice(&sender_lock_hash_h160);
+    args.extend_from_slice(&state_type_script.calc_script_hash().raw_data());
+    let lock_script = context
+        .build_script(&out_point, Bytes::from(args))
+        .expect("script");
+    let lock_script_dep = CellDep::new_builder().out_point(out_point).build();
+
+    let secp256k1_data_bin = BUNDLED_CELL.get("specs/cells/secp256k1_data").unwrap();
+    let secp256k1_data_out_point = context.deploy_cell(secp256k1_data_bin.to_vec().into());
+    let secp256k1_data_dep = CellDep::new_builder()
+        .out_point(secp256k1_data_out_point)
+        .build();
+
+    (
+        lock_script,
+        state_type_script,
+        vec![lock_script_dep, c_cell_dep, secp256k1_data_dep],
+    )
+}
+
+fn build_pledge_input(
+    context: &mut Context,
+    lock_script: &Script,
+    capacity: u64,
+    data: Bytes,
+    since: u64,
+) -> CellInput {
+    let input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(lock_script.clone())
+            .build(),
+        data,
+    );
+    CellInput::new_builder()
+        .previous_output(input_out_point)
+        .since(since.pack())
+        .build()
+}
+
+// the action 1 byte and the args of the action
+fn build_action_witness(action: u8, args: &[u8]) -> Bytes {
+    let mut input_type = vec![action];
+    input_type.extend_from_slice(args);
+    WitnessArgs::new_builder()
+        .input_type(Some(Bytes::from(input_type)).pack())
+        .build()
+        .as_bytes()
+}
+
+fn build_tx(
+    context: &mut Context,
+    inputs: Vec<CellInput>,
+    outputs: Vec<(CellOutput, Bytes)>,
+    cell_deps: Vec<CellDep>,
+    witness: Bytes,
+    key: &str,
+) -> TransactionView {
+    let (outputs, outputs_data): (Vec<_>, Vec<_>) = outputs.into_iter().unzip();
+    let tx = TransactionBuilder::default()
+        .inputs(inputs)
+        .outputs(outputs)
+        .outputs_data(outputs_data.pack())
+        .cell_deps(cell_deps)
+        .witness(witness.pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let private_key = Privkey::from_str(key).unwrap();
+    sign_tx(tx, &private_key)
+}
+
 #[test]
 fn test_receiver_success() {
     // deploy contract
@@ -89,9 +465,11 @@ fn test_receiver_success() {
     let (sender_address, sender_key, sender_lock_hash_h160) = prepare_sender_key();
 
     // prepare scripts
+    let state_type_script = prepare_state_type_script(&mut context, &data_hash, &STATE_TYPE_ID);
     let mut args = data_hash.to_vec();
     args.extend_from_slice(&receiver_lock_hash_h160);
     args.extend_from_slice(&sender_lock_hash_h160);
+    args.extend_from_slice(&state_type_script.calc_script_hash().raw_data());
     let args = Bytes::from(args);
     let lock_script = context.build_script(&out_point, args).expect("script");
     let lock_script_dep = CellDep::new_builder().out_point(out_point).build();
@@ -106,26 +484,47 @@ fn test_receiver_success() {
     // prepare cells
     let input_out_point = context.create_cell(
         CellOutput::new_builder()
-            .capacity(1000u64.pack())
+            .capacity((300 * SHANNONS_PER_CKB).pack())
             .lock(lock_script.clone())
             .build(),
         Bytes::new(),
     );
+    // the startup amount can be claimed since the pledge time
     let input = CellInput::new_builder()
         .previous_output(input_out_point)
+        .since(absolute_epoch_since(10).pack())
         .build();
+    // the project gets funded with the pledges in the transaction
+    let state_input = build_state_input(
+        &mut context,
+        &state_type_script,
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+        absolute_epoch_since(10),
+    );
+    let state_output = build_state_output(&mut context, &state_type_script);
+    let creator_lock_script = prepare_creator_lock_script(&mut context);
     let outputs = vec![
         CellOutput::new_builder()
-            .capacity(500u64.pack())
-            .lock(lock_script.clone())
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(lock_script)
             .build(),
         CellOutput::new_builder()
-            .capacity(500u64.pack())
-            .lock(lock_script)
+            .capacity((100 * SHANNONS_PER_CKB).pack())
+            .lock(creator_lock_script)
             .build(),
+        state_output,
     ];
 
-    let outputs_data = vec![Bytes::new(); 2];
+    let outputs_data = [
+        Bytes::from(vec![1]),
+        Bytes::new(),
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ];
 
     let mut witnesses = vec![];
     witnesses.push(Bytes::new());
@@ -133,6 +532,7 @@ fn test_receiver_success() {
     // build transaction
     let tx = TransactionBuilder::default()
         .input(input)
+        .input(state_input)
         .outputs(outputs)
         .outputs_data(outputs_data.pack())
         .cell_dep(lock_script_dep)
@@ -168,9 +568,11 @@ fn test_sender_success() {
     let (sender_address, sender_key, sender_lock_hash_h160) = prepare_sender_key();
 
     // prepare scripts
+    let state_type_script = prepare_state_type_script(&mut context, &data_hash, &STATE_TYPE_ID);
     let mut args = data_hash.to_vec();
     args.extend_from_slice(&receiver_lock_hash_h160);
     args.extend_from_slice(&sender_lock_hash_h160);
+    args.extend_from_slice(&state_type_script.calc_script_hash().raw_data());
     let args = Bytes::from(args);
     let lock_script = context.build_script(&out_point, args).expect("script");
     let lock_script_dep = CellDep::new_builder().out_point(out_point).build();
@@ -206,6 +608,13 @@ fn test_sender_success() {
 
     let outputs_data = vec![Bytes::new(); 2];
 
+    // the project is still pledging
+    let state_dep = build_state_dep(
+        &mut context,
+        &state_type_script,
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+    );
+
     let mut witnesses = vec![];
     witnesses.push(Bytes::new());
 
@@ -217,6 +626,7 @@ fn test_sender_success() {
         .cell_dep(lock_script_dep)
         .cell_dep(c_cell_dep)
         .cell_dep(secp256k1_data_dep)
+        .cell_dep(state_dep)
         .witnesses(witnesses.pack())
         .build();
     let tx = context.complete_tx(tx);
@@ -230,3 +640,4543 @@ fn test_sender_success() {
         .expect("pass verification");
     println!("consume cycles: {}", cycles);
 }
+
+#[test]
+fn test_receiver_claim_more_than_pro_rata_share_failed() {
+    let mut context = Context::default();
+    let (lock_script, state_type_script, cell_deps) = prepare_lock_script(&mut context);
+    let creator_lock_script = prepare_creator_lock_script(&mut context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    // the first milestone releases 50 CKB since epoch 20, all the pledges are in the cell
+    let input = build_pledge_input(
+        &mut context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        0,
+    );
+    let state_input = build_state_input(
+        &mut context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+        absolute_epoch_since(20),
+    );
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((150 * SHANNONS_PER_CKB - 1).pack())
+                .lock(lock_script)
+                .build(),
+            Bytes::from(vec![2]),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((50 * SHANNONS_PER_CKB + 1).pack())
+                .lock(creator_lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+        (
+            build_state_output(&mut context, &state_type_script),
+            build_state(
+                PHASE_MILESTONE,
+                0,
+                300 * SHANNONS_PER_CKB,
+                150 * SHANNONS_PER_CKB,
+            ),
+        ),
+    ];
+    let tx = build_tx(
+        &mut context,
+        vec![input, state_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RELEASE_CAPACITY_WRONG);
+}
+
+#[test]
+fn test_receiver_claim_milestone_before_time_failed() {
+    let mut context = Context::default();
+    let (lock_script, state_type_script, cell_deps) = prepare_lock_script(&mut context);
+    let creator_lock_script = prepare_creator_lock_script(&mut context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    let input = build_pledge_input(
+        &mut context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        0,
+    );
+    let state_input = build_state_input(
+        &mut context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+        absolute_epoch_since(19),
+    );
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((150 * SHANNONS_PER_CKB).pack())
+                .lock(lock_script)
+                .build(),
+            Bytes::from(vec![2]),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((50 * SHANNONS_PER_CKB).pack())
+                .lock(creator_lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+        (
+            build_state_output(&mut context, &state_type_script),
+            build_state(
+                PHASE_MILESTONE,
+                0,
+                300 * SHANNONS_PER_CKB,
+                150 * SHANNONS_PER_CKB,
+            ),
+        ),
+    ];
+    let tx = build_tx(
+        &mut context,
+        vec![input, state_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_STATE_TRANSITION_TOO_EARLY);
+}
+
+#[test]
+fn test_sender_refund_after_milestone_deadline_success() {
+    let mut context = Context::default();
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script(&mut context);
+    let (_, sender_key, _) = prepare_sender_key();
+
+    // the startup amount has been released, the first milestone (epoch 20) fails after the grace period
+    cell_deps.push(build_state_dep(
+        &mut context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let input = build_pledge_input(
+        &mut context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        absolute_epoch_since(20 + 42),
+    );
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(prepare_creator_lock_script(&mut context))
+            .build(),
+        Bytes::new(),
+    )];
+    let tx = build_tx(
+        &mut context,
+        vec![input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    );
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sender_refund_before_milestone_deadline_failed() {
+    let mut context = Context::default();
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script(&mut context);
+    let (_, sender_key, _) = prepare_sender_key();
+
+    cell_deps.push(build_state_dep(
+        &mut context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let input = build_pledge_input(
+        &mut context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        absolute_epoch_since(20 + 41),
+    );
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(prepare_creator_lock_script(&mut context))
+            .build(),
+        Bytes::new(),
+    )];
+    let tx = build_tx(
+        &mut context,
+        vec![input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_PROJECT_NOT_FAILED);
+}
+
+// The state has released the last milestone (epoch 30) without the approval of the backers,
+// so the creator never collects it from the pledge, which is refunded after the grace period
+fn build_uncollected_refund_tx(context: &mut Context, since_epoch: u64) -> TransactionView {
+    build_uncollected_refund_tx_of_pledge(context, 2, since_epoch)
+}
+
+// the pledge has paid the releases before the one the creator has not collected
+fn build_uncollected_refund_tx_of_pledge(
+    context: &mut Context,
+    released: u8,
+    since_epoch: u64,
+) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script(context);
+    let (_, sender_key, _) = prepare_sender_key();
+
+    cell_deps.push(build_state_dep(
+        context,
+        &state_type_script,
+        build_state(
+            PHASE_COMPLETED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            300 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let input = build_pledge_input(
+        context,
+        &lock_script,
+        100 * SHANNONS_PER_CKB,
+        Bytes::from(vec![released]),
+        absolute_epoch_since(since_epoch),
+    );
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((100 * SHANNONS_PER_CKB).pack())
+            .lock(prepare_creator_lock_script(context))
+            .build(),
+        Bytes::new(),
+    )];
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_sender_refund_uncollected_release_success() {
+    let mut context = Context::default();
+    let tx = build_uncollected_refund_tx(&mut context, 30 + 42);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sender_refund_uncollected_release_in_grace_period_failed() {
+    let mut context = Context::default();
+    let tx = build_uncollected_refund_tx(&mut context, 30 + 41);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RELEASE_NOT_COLLECTED);
+}
+
+#[test]
+fn test_sender_refund_first_milestone_uncollected_of_completed_state_success() {
+    let mut context = Context::default();
+    // the creator has completed the state alone, the first milestone (epoch 20) is due from the pledge
+    let tx = build_uncollected_refund_tx_of_pledge(&mut context, 1, 20 + 42);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// The creator cancels the project with the state cell under the creator lock, without touching the pledges,
+// or the state cell is under another lock and the creator has no input in the transaction
+fn build_cancel_tx(context: &mut Context, by_creator: bool) -> TransactionView {
+    let (_, state_type_script, cell_deps) = prepare_lock_script(context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    let state_data = build_state(
+        PHASE_FUNDED,
+        0,
+        300 * SHANNONS_PER_CKB,
+        100 * SHANNONS_PER_CKB,
+    );
+    let state_input = if by_creator {
+        build_state_input(context, &state_type_script, state_data, 0)
+    } else {
+        let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+        let other_lock_script = context
+            .build_script(&always_success_out_point, Bytes::from(vec![1]))
+            .expect("other lock script");
+        let cell = build_state_output(context, &state_type_script)
+            .as_builder()
+            .lock(other_lock_script)
+            .build();
+        CellInput::new_builder()
+            .previous_output(create_funding_cell(context, cell, state_data))
+            .build()
+    };
+    let outputs = vec![(
+        build_state_output(context, &state_type_script),
+        build_state(
+            PHASE_CANCELLED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    )];
+    build_tx(
+        context,
+        vec![state_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_creator_cancel_success() {
+    let mut context = Context::default();
+    let tx = build_cancel_tx(&mut context, true);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_cancel_without_creator_input_failed() {
+    let mut context = Context::default();
+    let tx = build_cancel_tx(&mut context, false);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_STATE_CREATOR_NOT_SIGNED);
+}
+
+#[test]
+fn test_receiver_claim_after_cancel_failed() {
+    let mut context = Context::default();
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script(&mut context);
+    let creator_lock_script = prepare_creator_lock_script(&mut context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    cell_deps.push(build_state_dep(
+        &mut context,
+        &state_type_script,
+        build_state(
+            PHASE_CANCELLED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let input = build_pledge_input(
+        &mut context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        absolute_epoch_since(20),
+    );
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((150 * SHANNONS_PER_CKB).pack())
+                .lock(lock_script)
+                .build(),
+            Bytes::from(vec![2]),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((50 * SHANNONS_PER_CKB).pack())
+                .lock(creator_lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+    ];
+    let tx = build_tx(
+        &mut context,
+        vec![input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_PROJECT_CANCELLED);
+}
+
+#[test]
+fn test_sender_refund_after_cancel_success() {
+    let mut context = Context::default();
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script(&mut context);
+    let (_, sender_key, _) = prepare_sender_key();
+
+    // no need to wait for the milestone deadline once the project is cancelled
+    cell_deps.push(build_state_dep(
+        &mut context,
+        &state_type_script,
+        build_state(
+            PHASE_CANCELLED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let input = build_pledge_input(
+        &mut context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        0,
+    );
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(prepare_creator_lock_script(&mut context))
+            .build(),
+        Bytes::new(),
+    )];
+    let tx = build_tx(
+        &mut context,
+        vec![input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    );
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_invalid_action_failed() {
+    let mut context = Context::default();
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script(&mut context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    cell_deps.push(build_state_dep(
+        &mut context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let input = build_pledge_input(
+        &mut context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        0,
+    );
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(lock_script)
+            .build(),
+        Bytes::from(vec![1]),
+    )];
+    let tx = build_tx(
+        &mut context,
+        vec![input],
+        outputs,
+        cell_deps,
+        build_action_witness(ACTION_INVALID, &[]),
+        &receiver_key,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_INVALID_ACTION);
+}
+
+#[test]
+fn test_state_create_success() {
+    let mut context = Context::default();
+    let (_, data_hash) = prepare_c_cell(&mut context);
+    let creator_lock_script = prepare_creator_lock_script(&mut context);
+
+    let input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((500 * SHANNONS_PER_CKB).pack())
+            .lock(creator_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let input = CellInput::new_builder()
+        .previous_output(input_out_point)
+        .build();
+
+    // the type id of the state cell at the output 0
+    let mut type_id_data = input.as_slice().to_vec();
+    type_id_data.extend_from_slice(&0u64.to_le_bytes());
+    let type_id = blake2b_256(type_id_data);
+    let state_type_script = prepare_state_type_script(&mut context, &data_hash, &type_id);
+
+    let outputs = vec![(
+        build_state_output(&mut context, &state_type_script),
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+    )];
+    let (_, receiver_key, _) = parepare_receiver_key();
+    let tx = build_tx(
+        &mut context,
+        vec![input],
+        outputs,
+        vec![],
+        Bytes::new(),
+        &receiver_key,
+    );
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_state_skip_release_failed() {
+    let mut context = Context::default();
+    let (_, state_type_script, cell_deps) = prepare_lock_script(&mut context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    // the first milestone can't be released before the startup amount
+    let state_input = build_state_input(
+        &mut context,
+        &state_type_script,
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+        absolute_epoch_since(20),
+    );
+    let outputs = vec![(
+        build_state_output(&mut context, &state_type_script),
+        build_state(
+            PHASE_MILESTONE,
+            0,
+            300 * SHANNONS_PER_CKB,
+            50 * SHANNONS_PER_CKB,
+        ),
+    )];
+    let tx = build_tx(
+        &mut context,
+        vec![state_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_STATE_INVALID_TRANSITION);
+}
+
+#[test]
+fn test_state_funding_without_pledges_failed() {
+    let mut context = Context::default();
+    let (_, state_type_script, cell_deps) = prepare_lock_script(&mut context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    // the creator records a total pledged no pledge cell in the transaction adds up to
+    let state_input = build_state_input(
+        &mut context,
+        &state_type_script,
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+        absolute_epoch_since(10),
+    );
+    let outputs = vec![(
+        build_state_output(&mut context, &state_type_script),
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    )];
+    let tx = build_tx(
+        &mut context,
+        vec![state_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_STATE_TOTAL_PLEDGED_WRONG);
+}
+
+fn build_pledge_with_receipt_tx(
+    context: &mut Context,
+    project_data: &[u8],
+    receipt_amount: u64,
+) -> TransactionView {
+    let (lock_script, _, cell_deps) = prepare_lock_script_of_project(context, project_data);
+    let receipt_type_script = prepare_receipt_type_script(context, &lock_script);
+    let backer_lock_script = prepare_creator_lock_script(context);
+    let (_, sender_key, sender_lock_hash_h160) = prepare_sender_key();
+
+    let input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((500 * SHANNONS_PER_CKB).pack())
+            .lock(backer_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let input = CellInput::new_builder()
+        .previous_output(input_out_point)
+        .build();
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((300 * SHANNONS_PER_CKB).pack())
+                .lock(lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((200 * SHANNONS_PER_CKB).pack())
+                .lock(backer_lock_script)
+                .type_(Some(receipt_type_script).pack())
+                .build(),
+            build_receipt(&sender_lock_hash_h160, receipt_amount),
+        ),
+    ];
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_receipt_mint_success() {
+    let mut context = Context::default();
+    let tx = build_pledge_with_receipt_tx(&mut context, &PROJECT_DATA, 300 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receipt_mint_more_than_pledged_failed() {
+    let mut context = Context::default();
+    let tx = build_pledge_with_receipt_tx(&mut context, &PROJECT_DATA, 300 * SHANNONS_PER_CKB + 1);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RECEIPT_MINT_AMOUNT_WRONG);
+}
+
+#[test]
+fn test_receipt_mint_less_than_pledged_failed() {
+    let mut context = Context::default();
+    let tx = build_pledge_with_receipt_tx(&mut context, &PROJECT_DATA, 300 * SHANNONS_PER_CKB - 1);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RECEIPT_MINT_AMOUNT_WRONG);
+}
+
+// the refund of a pledge of 200 CKB after the cancel, burning `burned` of its receipt
+fn build_receipt_burn_tx(context: &mut Context, burned: u64) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script(context);
+    let receipt_type_script = prepare_receipt_type_script(context, &lock_script);
+    let (_, sender_key, sender_lock_hash) = prepare_sender_key();
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let holder_lock_script = context
+        .build_script(&always_success_out_point, Bytes::new())
+        .expect("holder lock script");
+    let receipt_output = CellOutput::new_builder()
+        .capacity((100 * SHANNONS_PER_CKB).pack())
+        .lock(holder_lock_script)
+        .type_(Some(receipt_type_script).pack())
+        .build();
+
+    cell_deps.push(build_state_dep(
+        context,
+        &state_type_script,
+        build_state(
+            PHASE_CANCELLED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let pledge_input = build_pledge_input(
+        context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        0,
+    );
+    let receipt_out_point = context.create_cell(
+        receipt_output.clone(),
+        build_receipt(&sender_lock_hash, 200 * SHANNONS_PER_CKB),
+    );
+    let receipt_input = CellInput::new_builder()
+        .previous_output(receipt_out_point)
+        .build();
+    let mut outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(prepare_creator_lock_script(context))
+            .build(),
+        Bytes::new(),
+    )];
+    if burned < 200 * SHANNONS_PER_CKB {
+        outputs.push((
+            receipt_output,
+            build_receipt(&sender_lock_hash, 200 * SHANNONS_PER_CKB - burned),
+        ));
+    }
+    build_tx(
+        context,
+        vec![pledge_input, receipt_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_receipt_burn_with_refund_success() {
+    let mut context = Context::default();
+    let tx = build_receipt_burn_tx(&mut context, 200 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receipt_burn_less_than_refunded_failed() {
+    let mut context = Context::default();
+    let tx = build_receipt_burn_tx(&mut context, 100 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RECEIPT_BURN_AMOUNT_WRONG);
+}
+
+// the pledge lock of the project with args[start..start + bytes.len()] replaced
+fn replace_lock_args(lock_script: &Script, start: usize, bytes: &[u8]) -> Script {
+    let mut args = lock_script.args().raw_data().to_vec();
+    args[start..start + bytes.len()].copy_from_slice(bytes);
+    lock_script
+        .clone()
+        .as_builder()
+        .args(Bytes::from(args).pack())
+        .build()
+}
+
+// the backer transfers a pledge of 200 CKB to a new backer
+fn build_transfer_tx(
+    context: &mut Context,
+    project_id: Option<[u8; 32]>,
+    capacity: u64,
+) -> TransactionView {
+    let (lock_script, _, cell_deps) = prepare_lock_script(context);
+    let (_, sender_key, _) = prepare_sender_key();
+
+    let input = build_pledge_input(
+        context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        0,
+    );
+    let mut new_lock_script = replace_lock_args(&lock_script, 52, &[9u8; 20]);
+    if let Some(project_id) = project_id {
+        new_lock_script = replace_lock_args(&new_lock_script, 0, &project_id);
+    }
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(new_lock_script)
+            .build(),
+        Bytes::from(vec![1]),
+    )];
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        cell_deps,
+        build_action_witness(ACTION_TRANSFER, &[]),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_sender_transfer_success() {
+    let mut context = Context::default();
+    let tx = build_transfer_tx(&mut context, None, 200 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sender_transfer_change_capacity_failed() {
+    let mut context = Context::default();
+    let tx = build_transfer_tx(&mut context, None, 199 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_TRANSFER_CAPACITY_WRONG);
+}
+
+#[test]
+fn test_sender_transfer_to_other_project_failed() {
+    let mut context = Context::default();
+    let tx = build_transfer_tx(&mut context, Some([8u8; 32]), 200 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_TRANSFER_OUTPUTS_WRONG);
+}
+
+// the backer transfers the position of 100 CKB created by the funding transaction, or forged by another one
+fn build_position_transfer_tx(context: &mut Context, forged: bool) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps) =
+        prepare_lock_script_of_project(context, &build_project_data_v2(&[]));
+    let (_, sender_key, _) = prepare_sender_key();
+    cell_deps.push(build_state_dep(
+        context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+
+    let position_output = CellOutput::new_builder()
+        .capacity((50 * SHANNONS_PER_CKB).pack())
+        .lock(lock_script.clone())
+        .build();
+    let position_data = build_position_data(100 * SHANNONS_PER_CKB);
+    let position_out_point = if forged {
+        context.create_cell(position_output, position_data.clone())
+    } else {
+        create_funding_cell(context, position_output, position_data.clone())
+    };
+    let input = CellInput::new_builder()
+        .previous_output(position_out_point)
+        .build();
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(replace_lock_args(&lock_script, 52, &[9u8; 20]))
+            .build(),
+        position_data,
+    )];
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        cell_deps,
+        build_action_witness(ACTION_TRANSFER, &[]),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_sender_transfer_position_success() {
+    let mut context = Context::default();
+    let tx = build_position_transfer_tx(&mut context, false);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sender_transfer_forged_position_failed() {
+    let mut context = Context::default();
+    let tx = build_position_transfer_tx(&mut context, true);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_TRANSFER_OUTPUTS_WRONG);
+}
+
+// The backer transfers a pledge of 200 CKB with its receipt of 200 CKB to a new backer,
+// who gets the receipt amount and the backer keeps the rest of it
+fn build_receipt_transfer_tx(context: &mut Context, receipt_amount: u64) -> TransactionView {
+    let (lock_script, _, cell_deps) = prepare_lock_script(context);
+    let receipt_type_script = prepare_receipt_type_script(context, &lock_script);
+    let (_, sender_key, sender_lock_hash) = prepare_sender_key();
+    let new_sender_lock_hash = [9u8; 20];
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let holder_lock_script = context
+        .build_script(&always_success_out_point, Bytes::new())
+        .expect("holder lock script");
+    let receipt_output = CellOutput::new_builder()
+        .capacity((100 * SHANNONS_PER_CKB).pack())
+        .lock(holder_lock_script)
+        .type_(Some(receipt_type_script).pack())
+        .build();
+
+    let pledge_input = build_pledge_input(
+        context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        0,
+    );
+    let receipt_out_point = context.create_cell(
+        receipt_output.clone(),
+        build_receipt(&sender_lock_hash, 200 * SHANNONS_PER_CKB),
+    );
+    let receipt_input = CellInput::new_builder()
+        .previous_output(receipt_out_point)
+        .build();
+    let mut outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((200 * SHANNONS_PER_CKB).pack())
+                .lock(replace_lock_args(&lock_script, 52, &new_sender_lock_hash))
+                .build(),
+            Bytes::from(vec![1]),
+        ),
+        (
+            receipt_output.clone(),
+            build_receipt(&new_sender_lock_hash, receipt_amount),
+        ),
+    ];
+    if receipt_amount < 200 * SHANNONS_PER_CKB {
+        outputs.push((
+            receipt_output,
+            build_receipt(&sender_lock_hash, 200 * SHANNONS_PER_CKB - receipt_amount),
+        ));
+    }
+    build_tx(
+        context,
+        vec![pledge_input, receipt_input],
+        outputs,
+        cell_deps,
+        build_action_witness(ACTION_TRANSFER, &[]),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_receipt_transfer_success() {
+    let mut context = Context::default();
+    let tx = build_receipt_transfer_tx(&mut context, 200 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receipt_transfer_part_of_receipt_failed() {
+    let mut context = Context::default();
+    // the backer keeps a receipt of 100 CKB without any pledge left
+    let tx = build_receipt_transfer_tx(&mut context, 100 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RECEIPT_TRANSFER_AMOUNT_WRONG);
+}
+
+// the UDT amount 16 bytes and the pledge data
+fn build_udt_data(amount: u128, pledge_data: &[u8]) -> Bytes {
+    let mut data = amount.to_le_bytes().to_vec();
+    data.extend_from_slice(pledge_data);
+    Bytes::from(data)
+}
+
+// The project raises the UDT, the creator gets funded with 100 of the 300 UDT pledged
+fn build_udt_funding_tx(context: &mut Context, pledge_output_capacity: u64) -> TransactionView {
+    build_udt_funding_tx_of_project(context, pledge_output_capacity, &PROJECT_DATA, 1)
+}
+
+// The project data without the currency, and the UDT amounts in the unit
+fn build_udt_funding_tx_of_project(
+    context: &mut Context,
+    pledge_output_capacity: u64,
+    project_data: &[u8],
+    unit: u128,
+) -> TransactionView {
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let udt_type_script = context
+        .build_script(&always_success_out_point, Bytes::from(vec![1]))
+        .expect("udt type script");
+    let mut project_data = project_data.to_vec();
+    project_data.extend_from_slice(&udt_type_script.calc_script_hash().raw_data());
+    let (lock_script, state_type_script, cell_deps) =
+        prepare_lock_script_of_project(context, &project_data);
+    let creator_lock_script = prepare_creator_lock_script(context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    let input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(lock_script.clone())
+            .type_(Some(udt_type_script.clone()).pack())
+            .build(),
+        build_udt_data(300 * unit, &[]),
+    );
+    let input = CellInput::new_builder()
+        .previous_output(input_out_point)
+        .build();
+    let state_input = build_state_input(
+        context,
+        &state_type_script,
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+        absolute_epoch_since(10),
+    );
+    // the creator pays the capacity of the UDT cell taken
+    let creator_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((100 * SHANNONS_PER_CKB).pack())
+            .lock(creator_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let creator_input = CellInput::new_builder()
+        .previous_output(creator_out_point)
+        .build();
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity(pledge_output_capacity.pack())
+                .lock(lock_script)
+                .type_(Some(udt_type_script.clone()).pack())
+                .build(),
+            build_udt_data(200 * unit, &[1]),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((100 * SHANNONS_PER_CKB).pack())
+                .lock(creator_lock_script)
+                .type_(Some(udt_type_script).pack())
+                .build(),
+            build_udt_data(100 * unit, &[]),
+        ),
+        (
+            build_state_output(context, &state_type_script),
+            build_udt_state(PHASE_FUNDED, 300 * unit, 100 * unit),
+        ),
+    ];
+    build_tx(
+        context,
+        vec![input, state_input, creator_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_receiver_udt_claim_success() {
+    let mut context = Context::default();
+    let tx = build_udt_funding_tx(&mut context, 200 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_udt_claim_capacity_failed() {
+    let mut context = Context::default();
+    let tx = build_udt_funding_tx(&mut context, 150 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_PLEDGE_CAPACITY_NOT_CONSERVED);
+}
+
+// the state of the phase with the UDT amounts beyond the CKB amounts
+fn build_udt_state(phase: u8, total_pledged: u128, total_released: u128) -> Bytes {
+    let mut data = vec![phase, 0];
+    data.extend_from_slice(&total_pledged.to_be_bytes());
+    data.extend_from_slice(&total_released.to_be_bytes());
+    Bytes::from(data)
+}
+
+#[test]
+fn test_receiver_udt_claim_large_amounts_success() {
+    let mut context = Context::default();
+    // the pledge of 3 * 10^32 UDT times the startup amount of 10^32 UDT overflows u128
+    let unit = 10u128.pow(30);
+    let mut project_data = vec![1];
+    project_data.extend_from_slice(&10u64.to_be_bytes());
+    project_data.extend_from_slice(&(200 * unit).to_be_bytes());
+    project_data.extend_from_slice(&(100 * unit).to_be_bytes());
+    project_data.extend_from_slice(&20u64.to_be_bytes());
+    project_data.extend_from_slice(&(50 * unit).to_be_bytes());
+    project_data.push(6);
+    let tx =
+        build_udt_funding_tx_of_project(&mut context, 200 * SHANNONS_PER_CKB, &project_data, unit);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// the creator gets funded with the startup amount of 100 CKB and 50 shannons from the pledge of 300 CKB
+fn build_v1_funding_tx(context: &mut Context, pledge_output_capacity: u64) -> TransactionView {
+    let (lock_script, state_type_script, cell_deps) =
+        prepare_lock_script_of_project(context, &build_project_data_v1());
+    let creator_lock_script = prepare_creator_lock_script(context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    let input = build_pledge_input(
+        context,
+        &lock_script,
+        300 * SHANNONS_PER_CKB,
+        Bytes::new(),
+        0,
+    );
+    let state_input = build_state_input(
+        context,
+        &state_type_script,
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+        absolute_epoch_since(10),
+    );
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity(pledge_output_capacity.pack())
+                .lock(lock_script)
+                .build(),
+            Bytes::from(vec![1]),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((300 * SHANNONS_PER_CKB - pledge_output_capacity).pack())
+                .lock(creator_lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+        (
+            build_state_output(context, &state_type_script),
+            build_state(
+                PHASE_FUNDED,
+                0,
+                300 * SHANNONS_PER_CKB,
+                100 * SHANNONS_PER_CKB + 50,
+            ),
+        ),
+    ];
+    build_tx(
+        context,
+        vec![input, state_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_receiver_claim_v1_project_success() {
+    let mut context = Context::default();
+    let tx = build_v1_funding_tx(&mut context, 200 * SHANNONS_PER_CKB - 50);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_claim_v1_project_more_than_startup_failed() {
+    let mut context = Context::default();
+    let tx = build_v1_funding_tx(&mut context, 200 * SHANNONS_PER_CKB - 51);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RELEASE_CAPACITY_WRONG);
+}
+
+// The project raises 150 CKB, below the pledge threshold,
+// the creator takes 100 CKB and holds back 50 CKB for the milestone.
+fn build_v2_funding_tx(
+    context: &mut Context,
+    fields: &[(u8, Vec<u8>)],
+    witness: Bytes,
+) -> TransactionView {
+    build_v2_funding_tx_with_deps(context, fields, witness, vec![])
+}
+
+fn build_v2_funding_tx_with_deps(
+    context: &mut Context,
+    fields: &[(u8, Vec<u8>)],
+    witness: Bytes,
+    extra_cell_deps: Vec<CellDep>,
+) -> TransactionView {
+    build_v2_funding_tx_of_weight(context, fields, witness, extra_cell_deps, (10, None))
+}
+
+// the since of the state input, and the total weight of the backers in the quadratic voting
+// recorded by the funded state cell
+fn build_v2_funding_tx_of_weight(
+    context: &mut Context,
+    fields: &[(u8, Vec<u8>)],
+    witness: Bytes,
+    extra_cell_deps: Vec<CellDep>,
+    (state_since, total_weight): (u64, Option<u64>),
+) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps) =
+        prepare_lock_script_of_project(context, &build_project_data_v2(fields));
+    cell_deps.extend(extra_cell_deps);
+    let creator_lock_script = prepare_creator_lock_script(context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    let input = build_pledge_input(
+        context,
+        &lock_script,
+        150 * SHANNONS_PER_CKB,
+        Bytes::new(),
+        0,
+    );
+    let state_input = build_state_input(
+        context,
+        &state_type_script,
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+        absolute_epoch_since(state_since),
+    );
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((50 * SHANNONS_PER_CKB).pack())
+                .lock(lock_script)
+                .build(),
+            Bytes::from(vec![1]),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((100 * SHANNONS_PER_CKB).pack())
+                .lock(creator_lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+        (
+            build_state_output(context, &state_type_script),
+            build_state_of_weight(
+                PHASE_FUNDED,
+                0,
+                150 * SHANNONS_PER_CKB,
+                100 * SHANNONS_PER_CKB,
+                total_weight,
+            ),
+        ),
+    ];
+    build_tx(
+        context,
+        vec![input, state_input],
+        outputs,
+        cell_deps,
+        witness,
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_receiver_keep_it_all_claim_below_threshold_success() {
+    let mut context = Context::default();
+    let tx = build_v2_funding_tx(
+        &mut context,
+        &[(FIELD_MODE, vec![MODE_KEEP_IT_ALL])],
+        Bytes::new(),
+    );
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_all_or_nothing_claim_below_threshold_failed() {
+    let mut context = Context::default();
+    let tx = build_v2_funding_tx(
+        &mut context,
+        &[(FIELD_MODE, vec![MODE_ALL_OR_NOTHING])],
+        Bytes::new(),
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_STATE_PLEDGE_THRESHOLD_NOT_REACHED);
+}
+
+#[test]
+fn test_receiver_claim_duplicated_field_failed() {
+    let mut context = Context::default();
+    let tx = build_v2_funding_tx(
+        &mut context,
+        &[
+            (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+            (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+        ],
+        Bytes::new(),
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_CCELL_INVALID_DATA);
+}
+
+#[test]
+fn test_sender_keep_it_all_refund_while_pledging_failed() {
+    let mut context = Context::default();
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script_of_project(
+        &mut context,
+        &build_project_data_v2(&[(FIELD_MODE, vec![MODE_KEEP_IT_ALL])]),
+    );
+    let (_, sender_key, _) = prepare_sender_key();
+
+    // the pledges are committed until the creator misses the startup amount
+    cell_deps.push(build_state_dep(
+        &mut context,
+        &state_type_script,
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+    ));
+    let input = build_pledge_input(
+        &mut context,
+        &lock_script,
+        150 * SHANNONS_PER_CKB,
+        Bytes::new(),
+        absolute_epoch_since(10 + 41),
+    );
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((150 * SHANNONS_PER_CKB).pack())
+            .lock(prepare_creator_lock_script(&mut context))
+            .build(),
+        Bytes::new(),
+    )];
+    let tx = build_tx(
+        &mut context,
+        vec![input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_PROJECT_NOT_FAILED);
+}
+
+#[test]
+fn test_receiver_claim_over_hard_cap_failed() {
+    let mut context = Context::default();
+    let hard_cap = 140 * SHANNONS_PER_CKB as u128;
+    let tx = build_v2_funding_tx(
+        &mut context,
+        &[
+            (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+            (FIELD_HARD_CAP, hard_cap.to_be_bytes().to_vec()),
+        ],
+        Bytes::new(),
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_HARD_CAP_EXCEEDED);
+}
+
+#[test]
+fn test_receiver_claim_pledge_above_maximum_failed() {
+    let mut context = Context::default();
+    let max_pledge = 100 * SHANNONS_PER_CKB as u128;
+    let tx = build_v2_funding_tx(
+        &mut context,
+        &[
+            (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+            (FIELD_MAX_PLEDGE, max_pledge.to_be_bytes().to_vec()),
+        ],
+        Bytes::new(),
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_PLEDGE_ABOVE_MAXIMUM);
+}
+
+#[test]
+fn test_receipt_mint_pledge_below_minimum_failed() {
+    let mut context = Context::default();
+    let min_pledge = 400 * SHANNONS_PER_CKB as u128;
+    let project_data =
+        build_project_data_v2(&[(FIELD_MIN_PLEDGE, min_pledge.to_be_bytes().to_vec())]);
+    let tx = build_pledge_with_receipt_tx(&mut context, &project_data, 300 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RECEIPT_PLEDGE_BELOW_MINIMUM);
+}
+
+// the allow-list of the sender and another backer, return the root and the proof of the sender
+fn build_allow_list() -> ([u8; 32], Vec<u8>) {
+    let (_, _, sender_lock_hash_h160) = prepare_sender_key();
+    let sender_leaf = blake2b_256(sender_lock_hash_h160);
+    let other_leaf = blake2b_256([9u8; 20]);
+    let mut pair = vec![];
+    if sender_leaf <= other_leaf {
+        pair.extend_from_slice(&sender_leaf);
+        pair.extend_from_slice(&other_leaf);
+    } else {
+        pair.extend_from_slice(&other_leaf);
+        pair.extend_from_slice(&sender_leaf);
+    }
+    (blake2b_256(pair), other_leaf.to_vec())
+}
+
+#[test]
+fn test_receiver_claim_allowed_backer_success() {
+    let mut context = Context::default();
+    let (root, proof) = build_allow_list();
+    let tx = build_v2_funding_tx(
+        &mut context,
+        &[
+            (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+            (FIELD_ALLOW_LIST, root.to_vec()),
+        ],
+        build_action_witness(ACTION_DEFAULT, &proof),
+    );
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_claim_backer_not_allowed_failed() {
+    let mut context = Context::default();
+    let (root, _) = build_allow_list();
+    let tx = build_v2_funding_tx(
+        &mut context,
+        &[
+            (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+            (FIELD_ALLOW_LIST, root.to_vec()),
+        ],
+        Bytes::new(),
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_BACKER_NOT_ALLOWED);
+}
+
+// the issuer of the attestations, return the key and the blake160 of its public key
+fn prepare_issuer_key() -> (Privkey, [u8; 20]) {
+    let key = Privkey::from_str("6f7c9e5b1d2a4c3e8f0a1b2c3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4f506")
+        .unwrap();
+    let mut pubkey_hash = [0u8; 20];
+    pubkey_hash.copy_from_slice(&blake2b_256(key.pubkey().unwrap().serialize())[0..20]);
+    (key, pubkey_hash)
+}
+
+// the attestation of the backer to the project: backer 20 bytes, expiry epoch 8 bytes, signature 65 bytes,
+// and the message signed: project id 32 bytes, issuer 20 bytes, backer 20 bytes, expiry epoch 8 bytes
+fn build_attestation_dep(
+    context: &mut Context,
+    key: &Privkey,
+    project_id: &[u8],
+    backer: &[u8; 20],
+    expiry: u64,
+) -> CellDep {
+    let (_, issuer_pubkey_hash) = prepare_issuer_key();
+    let mut data = backer.to_vec();
+    data.extend_from_slice(&expiry.to_be_bytes());
+    let mut message = project_id.to_vec();
+    message.extend_from_slice(&issuer_pubkey_hash);
+    message.extend_from_slice(&data);
+    let message = H256::from(blake2b_256(&message));
+    let signature = key.sign_recoverable(&message).expect("sign");
+    data.extend_from_slice(&signature.serialize());
+    let cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(prepare_creator_lock_script(context))
+        .build();
+    let out_point = context.create_cell(cell, Bytes::from(data));
+    CellDep::new_builder().out_point(out_point).build()
+}
+
+fn build_kyc_funding_tx(context: &mut Context, key: &Privkey, expiry: u64) -> TransactionView {
+    build_kyc_funding_tx_of_attestation(context, key, None, expiry, 10)
+}
+
+// the attestation to the project or to another one, and the project funded since the epoch
+fn build_kyc_funding_tx_of_attestation(
+    context: &mut Context,
+    key: &Privkey,
+    other_project_id: Option<[u8; 32]>,
+    expiry: u64,
+    funding_epoch: u64,
+) -> TransactionView {
+    let (_, issuer_pubkey_hash) = prepare_issuer_key();
+    let (_, _, sender_lock_hash_h160) = prepare_sender_key();
+    let fields = [
+        (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+        (FIELD_KYC_ISSUER, issuer_pubkey_hash.to_vec()),
+    ];
+    let project_id =
+        other_project_id.unwrap_or_else(|| blake2b_256(build_project_data_v2(&fields)));
+    let attestation_dep =
+        build_attestation_dep(context, key, &project_id, &sender_lock_hash_h160, expiry);
+    build_v2_funding_tx_of_weight(
+        context,
+        &fields,
+        Bytes::new(),
+        vec![attestation_dep],
+        (funding_epoch, None),
+    )
+}
+
+#[test]
+fn test_receiver_claim_attested_backer_success() {
+    let mut context = Context::default();
+    let (issuer_key, _) = prepare_issuer_key();
+    let tx = build_kyc_funding_tx(&mut context, &issuer_key, 10);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_claim_attestation_of_other_issuer_failed() {
+    let mut context = Context::default();
+    let (_, receiver_key, _) = parepare_receiver_key();
+    let tx = build_kyc_funding_tx(&mut context, &Privkey::from_str(&receiver_key).unwrap(), 10);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_BACKER_NOT_ATTESTED);
+}
+
+#[test]
+fn test_receiver_claim_attestation_expired_failed() {
+    let mut context = Context::default();
+    let (issuer_key, _) = prepare_issuer_key();
+    let tx = build_kyc_funding_tx(&mut context, &issuer_key, 9);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_ATTESTATION_EXPIRED);
+}
+
+#[test]
+fn test_receiver_claim_attestation_expired_before_funding_failed() {
+    let mut context = Context::default();
+    let (issuer_key, _) = prepare_issuer_key();
+    // the attestation is valid at the pledge time 10, but expired when the project is funded at the epoch 15
+    let tx = build_kyc_funding_tx_of_attestation(&mut context, &issuer_key, None, 12, 15);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_ATTESTATION_EXPIRED);
+}
+
+#[test]
+fn test_receiver_claim_attestation_of_other_project_failed() {
+    let mut context = Context::default();
+    let (issuer_key, _) = prepare_issuer_key();
+    let tx =
+        build_kyc_funding_tx_of_attestation(&mut context, &issuer_key, Some([8u8; 32]), 10, 10);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_BACKER_NOT_ATTESTED);
+}
+
+// the lock of an operator or a payee, return it with its lock hash 160
+fn prepare_payout_lock_script(context: &mut Context, id: u8) -> (Script, [u8; 20]) {
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let lock_script = context
+        .build_script(&always_success_out_point, Bytes::from(vec![id]))
+        .expect("payout lock script");
+    let mut lock_hash = [0u8; 20];
+    lock_hash.copy_from_slice(&blake2b_256(lock_script.as_slice())[0..20]);
+    (lock_script, lock_hash)
+}
+
+// the startup release of 100 CKB of a keep-it-all project, the payouts are taken out of the creator output
+fn build_payout_funding_tx(
+    context: &mut Context,
+    fields: &[(u8, Vec<u8>)],
+    payouts: Vec<(Script, u64)>,
+) -> TransactionView {
+    let mut project_fields = vec![(FIELD_MODE, vec![MODE_KEEP_IT_ALL])];
+    project_fields.extend_from_slice(fields);
+    let (lock_script, state_type_script, cell_deps) =
+        prepare_lock_script_of_project(context, &build_project_data_v2(&project_fields));
+    let creator_lock_script = prepare_creator_lock_script(context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    let input = build_pledge_input(
+        context,
+        &lock_script,
+        150 * SHANNONS_PER_CKB,
+        Bytes::new(),
+        0,
+    );
+    let state_input = build_state_input(
+        context,
+        &state_type_script,
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+        absolute_epoch_since(10),
+    );
+    let payout_capacity: u64 = payouts.iter().map(|(_, capacity)| capacity).sum();
+    let mut outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((50 * SHANNONS_PER_CKB).pack())
+                .lock(lock_script)
+                .build(),
+            Bytes::from(vec![1]),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((100 * SHANNONS_PER_CKB - payout_capacity).pack())
+                .lock(creator_lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+        (
+            build_state_output(context, &state_type_script),
+            build_state(
+                PHASE_FUNDED,
+                0,
+                150 * SHANNONS_PER_CKB,
+                100 * SHANNONS_PER_CKB,
+            ),
+        ),
+    ];
+    for (lock_script, capacity) in payouts {
+        outputs.push((
+            CellOutput::new_builder()
+                .capacity(capacity.pack())
+                .lock(lock_script)
+                .build(),
+            Bytes::new(),
+        ));
+    }
+    build_tx(
+        context,
+        vec![input, state_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    )
+}
+
+// the platform fee of 5%, the startup release of 100 CKB pays 5 CKB to the operator
+fn build_fee_funding_tx(context: &mut Context, fee_capacity: u64) -> TransactionView {
+    let (operator_lock_script, operator_lock_hash) = prepare_payout_lock_script(context, 1);
+    let mut platform_fee = operator_lock_hash.to_vec();
+    platform_fee.extend_from_slice(&500u16.to_be_bytes());
+    build_payout_funding_tx(
+        context,
+        &[(FIELD_PLATFORM_FEE, platform_fee)],
+        vec![(operator_lock_script, fee_capacity)],
+    )
+}
+
+#[test]
+fn test_receiver_claim_with_platform_fee_success() {
+    let mut context = Context::default();
+    let tx = build_fee_funding_tx(&mut context, 5 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_claim_underpaid_platform_fee_failed() {
+    let mut context = Context::default();
+    let tx = build_fee_funding_tx(&mut context, 4 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_PLATFORM_FEE_WRONG);
+}
+
+// the payees of 60% and 40%, the startup release of 100 CKB is split into 60 CKB and 40 CKB
+fn build_payees_funding_tx(
+    context: &mut Context,
+    lead_capacity: u64,
+    designer_capacity: u64,
+) -> TransactionView {
+    let (lead_lock_script, lead_lock_hash) = prepare_payout_lock_script(context, 2);
+    let (designer_lock_script, designer_lock_hash) = prepare_payout_lock_script(context, 3);
+    let mut payees = lead_lock_hash.to_vec();
+    payees.extend_from_slice(&60u16.to_be_bytes());
+    payees.extend_from_slice(&designer_lock_hash);
+    payees.extend_from_slice(&40u16.to_be_bytes());
+    build_payout_funding_tx(
+        context,
+        &[(FIELD_PAYEES, payees)],
+        vec![
+            (lead_lock_script, lead_capacity),
+            (designer_lock_script, designer_capacity),
+        ],
+    )
+}
+
+#[test]
+fn test_receiver_claim_split_among_payees_success() {
+    let mut context = Context::default();
+    let tx = build_payees_funding_tx(&mut context, 60 * SHANNONS_PER_CKB, 40 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_claim_payee_underpaid_failed() {
+    let mut context = Context::default();
+    let tx = build_payees_funding_tx(&mut context, 70 * SHANNONS_PER_CKB, 30 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_PAYEE_AMOUNT_WRONG);
+}
+
+// a keep-it-all vesting project raised 150 CKB, the milestone of 50 CKB streams from the epoch 10 to 20,
+// the creator records the amount vested at the epoch 15 and takes it from the pledge settled at the startup amount
+fn build_vesting_claim_tx(context: &mut Context, total_released: u64) -> TransactionView {
+    build_vesting_claim_tx_of_project(
+        context,
+        &build_project_data_v2(&[
+            (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+            (FIELD_VESTING, vec![1]),
+        ]),
+        total_released,
+    )
+}
+
+fn build_vesting_claim_tx_of_project(
+    context: &mut Context,
+    project_data: &[u8],
+    total_released: u64,
+) -> TransactionView {
+    let (lock_script, state_type_script, cell_deps) =
+        prepare_lock_script_of_project(context, project_data);
+    let creator_lock_script = prepare_creator_lock_script(context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    let mut pledge_data = vec![1];
+    pledge_data.extend_from_slice(&(100 * SHANNONS_PER_CKB as u128).to_be_bytes());
+    let input = build_pledge_input(
+        context,
+        &lock_script,
+        50 * SHANNONS_PER_CKB,
+        Bytes::from(pledge_data),
+        0,
+    );
+    let state_input = build_state_input(
+        context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            150 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+        absolute_epoch_since(15),
+    );
+    let claimed = total_released - 100 * SHANNONS_PER_CKB;
+    let mut released_pledge_data = vec![1];
+    released_pledge_data.extend_from_slice(&(total_released as u128).to_be_bytes());
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((50 * SHANNONS_PER_CKB - claimed).pack())
+                .lock(lock_script)
+                .build(),
+            Bytes::from(released_pledge_data),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity(claimed.pack())
+                .lock(creator_lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+        (
+            build_state_output(context, &state_type_script),
+            build_funded_state(
+                PHASE_FUNDED,
+                0,
+                150 * SHANNONS_PER_CKB,
+                total_released,
+                None,
+            ),
+        ),
+    ];
+    build_tx(
+        context,
+        vec![input, state_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_receiver_claim_vested_amount_success() {
+    let mut context = Context::default();
+    let tx = build_vesting_claim_tx(&mut context, 125 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_claim_more_than_vested_failed() {
+    let mut context = Context::default();
+    let tx = build_vesting_claim_tx(&mut context, 130 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_STATE_RELEASED_AMOUNT_WRONG);
+}
+
+#[test]
+fn test_receiver_claim_vesting_project_with_vote_type_failed() {
+    let mut context = Context::default();
+    // the vesting projects have no escrow cells to weigh the votes in
+    let tx = build_vesting_claim_tx_of_project(
+        &mut context,
+        &build_project_data_v2(&[
+            (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+            (FIELD_VESTING, vec![1]),
+            (FIELD_VOTE_TYPE, vec![0u8; 33]),
+        ]),
+        125 * SHANNONS_PER_CKB,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_CCELL_INVALID_DATA);
+}
+
+// the escrow cell of the milestone is locked by the crowdfunding lock without the sender, with the milestone index
+fn build_escrow_lock_script(lock_script: &Script, milestone: u8) -> Script {
+    let mut args = lock_script.args().raw_data().to_vec();
+    args[52..72].copy_from_slice(&[0u8; 20]);
+    args.push(milestone);
+    lock_script
+        .clone()
+        .as_builder()
+        .args(Bytes::from(args).pack())
+        .build()
+}
+
+// position data: released 1 byte, settled 16 bytes, pledged 16 bytes
+fn build_position_data(pledged: u64) -> Bytes {
+    let mut data = vec![2];
+    data.extend_from_slice(&0u128.to_be_bytes());
+    data.extend_from_slice(&(pledged as u128).to_be_bytes());
+    Bytes::from(data)
+}
+
+// the creator finalizes the funding of 300 CKB, takes the startup amount of 100 CKB,
+// and moves the milestone of 50 CKB into its escrow cell, the backer keeps the rest 150 CKB in the position
+fn build_finalize_tx(context: &mut Context, escrow_capacity: u64) -> TransactionView {
+    let (lock_script, state_type_script, cell_deps) =
+        prepare_lock_script_of_project(context, &build_project_data_v2(&[]));
+    let creator_lock_script = prepare_creator_lock_script(context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    let input = build_pledge_input(
+        context,
+        &lock_script,
+        300 * SHANNONS_PER_CKB,
+        Bytes::new(),
+        0,
+    );
+    let state_input = build_state_input(
+        context,
+        &state_type_script,
+        build_state(PHASE_PLEDGING, 0, 0, 0),
+        absolute_epoch_since(10),
+    );
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((150 * SHANNONS_PER_CKB).pack())
+                .lock(lock_script.clone())
+                .build(),
+            build_position_data(300 * SHANNONS_PER_CKB),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity(escrow_capacity.pack())
+                .lock(build_escrow_lock_script(&lock_script, 0))
+                .build(),
+            Bytes::new(),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((250 * SHANNONS_PER_CKB - escrow_capacity).pack())
+                .lock(creator_lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+        (
+            build_state_output(context, &state_type_script),
+            build_state(
+                PHASE_FUNDED,
+                0,
+                300 * SHANNONS_PER_CKB,
+                100 * SHANNONS_PER_CKB,
+            ),
+        ),
+    ];
+    build_tx(
+        context,
+        vec![input, state_input],
+        outputs,
+        cell_deps,
+        build_action_witness(ACTION_FINALIZE, &[]),
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_receiver_finalize_funding_success() {
+    let mut context = Context::default();
+    let tx = build_finalize_tx(&mut context, 50 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_finalize_funding_escrow_amount_failed() {
+    let mut context = Context::default();
+    let tx = build_finalize_tx(&mut context, 40 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_ESCROW_OUTPUTS_WRONG);
+}
+
+// the creator takes the escrow cell of the first milestone
+fn build_escrow_release_tx(context: &mut Context, state_since: u64) -> TransactionView {
+    build_escrow_release_tx_of_project(
+        context,
+        &build_project_data_v2(&[]),
+        state_since,
+        |_, _| vec![],
+    )
+}
+
+fn build_escrow_release_tx_of_project<F: FnOnce(&mut Context, &Script) -> Vec<CellDep>>(
+    context: &mut Context,
+    project_data: &[u8],
+    state_since: u64,
+    extra_cell_deps: F,
+) -> TransactionView {
+    build_escrow_release_tx_of_escrow(
+        context,
+        project_data,
+        (state_since, None),
+        (Bytes::new(), 0),
+        Bytes::new(),
+        extra_cell_deps,
+    )
+}
+
+// the since of the state input and the total weight of the state,
+// the escrow cell with its data and the since of its input, and the witness of the release
+fn build_escrow_release_tx_of_escrow<F: FnOnce(&mut Context, &Script) -> Vec<CellDep>>(
+    context: &mut Context,
+    project_data: &[u8],
+    (state_since, total_weight): (u64, Option<u64>),
+    (escrow_data, escrow_since): (Bytes, u64),
+    witness: Bytes,
+    extra_cell_deps: F,
+) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps) =
+        prepare_lock_script_of_project(context, project_data);
+    cell_deps.extend(extra_cell_deps(context, &lock_script));
+    let creator_lock_script = prepare_creator_lock_script(context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    let escrow_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(build_escrow_lock_script(&lock_script, 0))
+            .build(),
+        escrow_data,
+    );
+    let escrow_input = CellInput::new_builder()
+        .previous_output(escrow_out_point)
+        .since(escrow_since.pack())
+        .build();
+    let state_input = build_state_input(
+        context,
+        &state_type_script,
+        build_state_of_weight(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+            total_weight,
+        ),
+        absolute_epoch_since(state_since),
+    );
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((50 * SHANNONS_PER_CKB).pack())
+                .lock(creator_lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+        (
+            build_state_output(context, &state_type_script),
+            build_funded_state(
+                PHASE_MILESTONE,
+                0,
+                300 * SHANNONS_PER_CKB,
+                150 * SHANNONS_PER_CKB,
+                total_weight,
+            ),
+        ),
+    ];
+    build_tx(
+        context,
+        vec![escrow_input, state_input],
+        outputs,
+        cell_deps,
+        witness,
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_receiver_release_escrow_success() {
+    let mut context = Context::default();
+    let tx = build_escrow_release_tx(&mut context, 20);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_release_escrow_before_milestone_failed() {
+    let mut context = Context::default();
+    let (lock_script, state_type_script, mut cell_deps) =
+        prepare_lock_script_of_project(&mut context, &build_project_data_v2(&[]));
+    let creator_lock_script = prepare_creator_lock_script(&mut context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    // the state cell has not released the milestone yet
+    cell_deps.push(build_state_dep(
+        &mut context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let escrow_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(build_escrow_lock_script(&lock_script, 0))
+            .build(),
+        Bytes::new(),
+    );
+    let escrow_input = CellInput::new_builder()
+        .previous_output(escrow_out_point)
+        .build();
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(creator_lock_script)
+            .build(),
+        Bytes::new(),
+    )];
+    let tx = build_tx(
+        &mut context,
+        vec![escrow_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RELEASE_TOO_EARLY);
+}
+
+// the project is cancelled after the finalize transaction, the backer of 100 CKB out of 300 CKB
+// takes the position and a third of the escrow cell, the rest of the escrow records the pledges not refunded
+fn build_escrow_refund_tx(context: &mut Context, remaining: u64) -> TransactionView {
+    build_escrow_refund_tx_of_state(
+        context,
+        remaining,
+        build_state(
+            PHASE_CANCELLED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+        0,
+    )
+}
+
+fn build_escrow_refund_tx_of_state(
+    context: &mut Context,
+    remaining: u64,
+    state_data: Bytes,
+    escrow_since: u64,
+) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps) =
+        prepare_lock_script_of_project(context, &build_project_data_v2(&[]));
+    let (_, sender_key, _) = prepare_sender_key();
+    cell_deps.push(build_state_dep(context, &state_type_script, state_data));
+
+    let finalize_tx_hash = [3u8; 32];
+    let position_out_point = OutPoint::new(finalize_tx_hash.pack(), 0);
+    context.create_cell_with_out_point(
+        position_out_point.clone(),
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(lock_script.clone())
+            .build(),
+        build_position_data(100 * SHANNONS_PER_CKB),
+    );
+    let escrow_lock_script = build_escrow_lock_script(&lock_script, 0);
+    let escrow_out_point = OutPoint::new(finalize_tx_hash.pack(), 1);
+    context.create_cell_with_out_point(
+        escrow_out_point.clone(),
+        CellOutput::new_builder()
+            .capacity((60 * SHANNONS_PER_CKB).pack())
+            .lock(escrow_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let inputs = vec![
+        CellInput::new_builder()
+            .previous_output(position_out_point)
+            .build(),
+        CellInput::new_builder()
+            .previous_output(escrow_out_point)
+            .since(escrow_since.pack())
+            .build(),
+    ];
+
+    let mut escrow_data = finalize_tx_hash.to_vec();
+    escrow_data.extend_from_slice(&(remaining as u128).to_be_bytes());
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((40 * SHANNONS_PER_CKB).pack())
+                .lock(escrow_lock_script)
+                .build(),
+            Bytes::from(escrow_data),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((70 * SHANNONS_PER_CKB).pack())
+                .lock(prepare_creator_lock_script(context))
+                .build(),
+            Bytes::new(),
+        ),
+    ];
+    build_tx(
+        context,
+        inputs,
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_sender_refund_from_escrow_success() {
+    let mut context = Context::default();
+    let tx = build_escrow_refund_tx(&mut context, 200 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sender_refund_from_escrow_remaining_wrong_failed() {
+    let mut context = Context::default();
+    let tx = build_escrow_refund_tx(&mut context, 300 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_ESCROW_OUTPUTS_WRONG);
+}
+
+// the state is completed without the backers approving the milestone, which the creator has not collected
+fn build_unapproved_escrow_refund_tx(context: &mut Context, escrow_since: u64) -> TransactionView {
+    build_escrow_refund_tx_of_state(
+        context,
+        200 * SHANNONS_PER_CKB,
+        build_state(
+            PHASE_COMPLETED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            300 * SHANNONS_PER_CKB,
+        ),
+        escrow_since,
+    )
+}
+
+#[test]
+fn test_sender_refund_from_unapproved_escrow_after_failed_epoch_success() {
+    let mut context = Context::default();
+    // milestone time 20 + grace period 42
+    let tx = build_unapproved_escrow_refund_tx(&mut context, absolute_epoch_since(62));
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sender_refund_from_unapproved_escrow_before_failed_epoch_failed() {
+    let mut context = Context::default();
+    let tx = build_unapproved_escrow_refund_tx(&mut context, absolute_epoch_since(61));
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_WRONG_PUB_KEY);
+}
+
+#[test]
+fn test_sender_refund_from_escrow_of_abandoned_project_success() {
+    let mut context = Context::default();
+    // the creator never moves the state past the startup amount
+    let tx = build_escrow_refund_tx_of_state(
+        &mut context,
+        200 * SHANNONS_PER_CKB,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+        absolute_epoch_since(62),
+    );
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// The backer bought the position of 100 CKB from the backer [9; 20] who got it from the funding transaction,
+// and takes a third of the escrow cell with the raw transactions moving it as the proof.
+fn build_moved_position_refund_tx(context: &mut Context, proved: bool) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps) =
+        prepare_lock_script_of_project(context, &build_project_data_v2(&[]));
+    let (_, sender_key, _) = prepare_sender_key();
+    let origin_lock_script = replace_lock_args(&lock_script, 52, &[9u8; 20]);
+    let escrow_lock_script = build_escrow_lock_script(&lock_script, 0);
+    let position_output = |lock: &Script| {
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(lock.clone())
+            .build()
+    };
+
+    let escrow_output = CellOutput::new_builder()
+        .capacity((60 * SHANNONS_PER_CKB).pack())
+        .lock(escrow_lock_script.clone())
+        .build();
+    let state_output = build_state_output(context, &state_type_script);
+    let state_data = build_state(
+        PHASE_CANCELLED,
+        0,
+        300 * SHANNONS_PER_CKB,
+        100 * SHANNONS_PER_CKB,
+    );
+    let funding_tx = TransactionBuilder::default()
+        .output(position_output(&origin_lock_script))
+        .output_data(build_position_data(100 * SHANNONS_PER_CKB).pack())
+        .output(escrow_output.clone())
+        .output_data(Bytes::new().pack())
+        .output(state_output.clone())
+        .output_data(state_data.pack())
+        .build();
+    let escrow_out_point = OutPoint::new(funding_tx.hash(), 1);
+    context.create_cell_with_out_point(escrow_out_point.clone(), escrow_output, Bytes::new());
+    let state_out_point = OutPoint::new(funding_tx.hash(), 2);
+    context.create_cell_with_out_point(state_out_point.clone(), state_output, state_data);
+    cell_deps.push(CellDep::new_builder().out_point(state_out_point).build());
+
+    let transfer_tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(OutPoint::new(funding_tx.hash(), 0))
+                .build(),
+        )
+        .output(position_output(&lock_script))
+        .output_data(build_position_data(100 * SHANNONS_PER_CKB).pack())
+        .build();
+    let position_out_point = OutPoint::new(transfer_tx.hash(), 0);
+    context.create_cell_with_out_point(
+        position_out_point.clone(),
+        position_output(&lock_script),
+        build_position_data(100 * SHANNONS_PER_CKB),
+    );
+
+    let inputs = vec![
+        CellInput::new_builder()
+            .previous_output(position_out_point)
+            .build(),
+        CellInput::new_builder()
+            .previous_output(escrow_out_point)
+            .build(),
+    ];
+    let mut escrow_data = funding_tx.hash().raw_data().to_vec();
+    escrow_data.extend_from_slice(&(200 * SHANNONS_PER_CKB as u128).to_be_bytes());
+    let outputs = vec![
+        (
+            CellOutput::new_builder()
+                .capacity((40 * SHANNONS_PER_CKB).pack())
+                .lock(escrow_lock_script)
+                .build(),
+            Bytes::from(escrow_data),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((70 * SHANNONS_PER_CKB).pack())
+                .lock(prepare_creator_lock_script(context))
+                .build(),
+            Bytes::new(),
+        ),
+    ];
+    let tx = build_tx(
+        context,
+        inputs,
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    );
+    if !proved {
+        return tx;
+    }
+    // the proof follows the witnesses of the inputs
+    let proof = BytesVec::new_builder()
+        .push(transfer_tx.data().raw().as_bytes().pack())
+        .push(funding_tx.data().raw().as_bytes().pack())
+        .build();
+    sign_tx(
+        tx.as_advanced_builder()
+            .witness(Bytes::new().pack())
+            .witness(proof.as_bytes().pack())
+            .build(),
+        &Privkey::from_str(&sender_key).unwrap(),
+    )
+}
+
+#[test]
+fn test_sender_refund_moved_position_from_escrow_success() {
+    let mut context = Context::default();
+    let tx = build_moved_position_refund_tx(&mut context, true);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sender_refund_moved_position_without_proof_failed() {
+    let mut context = Context::default();
+    let tx = build_moved_position_refund_tx(&mut context, false);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_NOTHING_TO_REFUND);
+}
+
+#[test]
+fn test_sender_withdraw_position_without_escrow_failed() {
+    let mut context = Context::default();
+    let (lock_script, state_type_script, mut cell_deps) =
+        prepare_lock_script_of_project(&mut context, &build_project_data_v2(&[]));
+    let (_, sender_key, _) = prepare_sender_key();
+    cell_deps.push(build_state_dep(
+        &mut context,
+        &state_type_script,
+        build_state(
+            PHASE_CANCELLED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let input = build_pledge_input(
+        &mut context,
+        &lock_script,
+        50 * SHANNONS_PER_CKB,
+        build_position_data(100 * SHANNONS_PER_CKB),
+        0,
+    );
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(prepare_creator_lock_script(&mut context))
+            .build(),
+        Bytes::new(),
+    )];
+    let tx = build_tx(
+        &mut context,
+        vec![input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_POSITION_NOT_REFUNDED);
+}
+
+// deploy the vote type script, return its out point and the vote type field of the project data
+fn deploy_vote_type_script(context: &mut Context) -> (OutPoint, Vec<u8>) {
+    let contract_bin: Bytes = Loader::default().load_binary("ckb-crowdfunding-vote");
+    let out_point = context.deploy_cell(contract_bin);
+    let script = context
+        .build_script(&out_point, Bytes::new())
+        .expect("vote type script");
+    let mut vote_type = script.code_hash().raw_data().to_vec();
+    vote_type.extend_from_slice(script.hash_type().as_slice());
+    (out_point, vote_type)
+}
+
+// the votes on the project of the crowdfunding lock
+fn build_vote_type_script(
+    context: &mut Context,
+    out_point: &OutPoint,
+    lock_script: &Script,
+) -> Script {
+    let mut args = lock_script.args().raw_data()[0..32].to_vec();
+    args.extend_from_slice(lock_script.code_hash().as_slice());
+    args.extend_from_slice(lock_script.hash_type().as_slice());
+    context
+        .build_script(out_point, Bytes::from(args))
+        .expect("vote type script")
+}
+
+// milestone index 1 byte, backer 20 bytes, vote 1 byte, weight 16 bytes
+fn build_vote(milestone: u8, backer: &[u8; 20], approve: bool, weight: u64) -> Bytes {
+    let mut data = vec![milestone];
+    data.extend_from_slice(backer);
+    data.push(approve as u8);
+    data.extend_from_slice(&(weight as u128).to_be_bytes());
+    Bytes::from(data)
+}
+
+// The backer votes in favor of the first milestone with 200 CKB out of 300 CKB,
+// and the position of the backer is in the cell deps with the pledge of the position
+fn build_voted_escrow_release_tx(
+    context: &mut Context,
+    quorum: u8,
+    pledged: u64,
+) -> TransactionView {
+    build_voted_escrow_release_tx_of_position(context, quorum, pledged, false)
+}
+
+// the position is created by the funding transaction, or forged by another transaction
+fn build_voted_escrow_release_tx_of_position(
+    context: &mut Context,
+    quorum: u8,
+    pledged: u64,
+    forged: bool,
+) -> TransactionView {
+    let (vote_out_point, vote_type) = deploy_vote_type_script(context);
+    let project_data =
+        build_project_data_v2(&[(FIELD_VOTE_TYPE, vote_type), (FIELD_QUORUMS, vec![quorum])]);
+    build_escrow_release_tx_of_project(context, &project_data, 20, |context, lock_script| {
+        let (_, _, sender_lock_hash) = prepare_sender_key();
+        let vote_type_script = build_vote_type_script(context, &vote_out_point, lock_script);
+        let voter_lock_script = prepare_creator_lock_script(context);
+        let vote_out_point = context.create_cell(
+            CellOutput::new_builder()
+                .capacity((100 * SHANNONS_PER_CKB).pack())
+                .lock(voter_lock_script)
+                .type_(Some(vote_type_script).pack())
+                .build(),
+            build_vote(0, &sender_lock_hash, true, 200 * SHANNONS_PER_CKB),
+        );
+        let position_output = CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(lock_script.clone())
+            .build();
+        let position_out_point = if forged {
+            context.create_cell(position_output, build_position_data(pledged))
+        } else {
+            create_funding_cell(context, position_output, build_position_data(pledged))
+        };
+        vec![
+            CellDep::new_builder().out_point(vote_out_point).build(),
+            CellDep::new_builder().out_point(position_out_point).build(),
+        ]
+    })
+}
+
+#[test]
+fn test_receiver_release_approved_escrow_success() {
+    let mut context = Context::default();
+    let tx = build_voted_escrow_release_tx(&mut context, 0, 200 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_release_escrow_vote_over_pledge_failed() {
+    let mut context = Context::default();
+    // the vote weighs only the 100 CKB pledged by the backer
+    let tx = build_voted_escrow_release_tx(&mut context, 0, 100 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_NOT_APPROVED);
+}
+
+#[test]
+fn test_receiver_release_escrow_vote_of_forged_position_failed() {
+    let mut context = Context::default();
+    // anyone can create a cell of the pledge lock, but only the positions of the funding transaction weigh
+    let tx =
+        build_voted_escrow_release_tx_of_position(&mut context, 0, 200 * SHANNONS_PER_CKB, true);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_NOT_APPROVED);
+}
+
+#[test]
+fn test_receiver_release_escrow_quorum_reached_success() {
+    let mut context = Context::default();
+    let tx = build_voted_escrow_release_tx(&mut context, 6, 200 * SHANNONS_PER_CKB);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_release_escrow_quorum_not_reached_failed() {
+    let mut context = Context::default();
+    // the votes of 200 CKB out of 300 CKB reach the approval ratio but not the quorum of 80%
+    let tx = build_voted_escrow_release_tx(&mut context, 8, 200 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_QUORUM_NOT_REACHED);
+}
+
+#[test]
+fn test_receiver_release_escrow_quorum_reached_ratio_not_reached_failed() {
+    let mut context = Context::default();
+    // the votes of 150 CKB out of 300 CKB reach the quorum of 30% but not the approval ratio
+    let tx = build_voted_escrow_release_tx(&mut context, 3, 150 * SHANNONS_PER_CKB);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_NOT_APPROVED);
+}
+
+#[test]
+fn test_receiver_release_escrow_without_votes_failed() {
+    let mut context = Context::default();
+    let (_, vote_type) = deploy_vote_type_script(&mut context);
+    let project_data = build_project_data_v2(&[(FIELD_VOTE_TYPE, vote_type)]);
+    let tx = build_escrow_release_tx_of_project(&mut context, &project_data, 20, |_, _| vec![]);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_NOT_APPROVED);
+}
+
+// delegator 20 bytes, delegate 20 bytes
+fn build_delegation(delegator: &[u8; 20], delegate: &[u8; 20]) -> Bytes {
+    let mut data = delegator.to_vec();
+    data.extend_from_slice(delegate);
+    Bytes::from(data)
+}
+
+// the backer votes with a cell of its own lock in the inputs
+fn build_vote_tx(context: &mut Context, input_lock_args: u8) -> TransactionView {
+    build_vote_tx_of_data(context, input_lock_args, |backer_lock_hash| {
+        build_vote(0, backer_lock_hash, true, 100 * SHANNONS_PER_CKB)
+    })
+}
+
+// the vote type cell of the data built from the backer lock hash
+fn build_vote_tx_of_data<F: FnOnce(&[u8; 20]) -> Bytes>(
+    context: &mut Context,
+    input_lock_args: u8,
+    data: F,
+) -> TransactionView {
+    build_vote_tx_of_outputs(context, input_lock_args, |backer_lock_hash| {
+        vec![data(backer_lock_hash)]
+    })
+}
+
+// the vote type cells of the data built from the backer lock hash
+fn build_vote_tx_of_outputs<F: FnOnce(&[u8; 20]) -> Vec<Bytes>>(
+    context: &mut Context,
+    input_lock_args: u8,
+    data: F,
+) -> TransactionView {
+    let (lock_script, _, _) = prepare_lock_script(context);
+    let (vote_out_point, _) = deploy_vote_type_script(context);
+    let vote_type_script = build_vote_type_script(context, &vote_out_point, &lock_script);
+    let backer_lock_script = prepare_creator_lock_script(context);
+    let mut backer_lock_hash = [0u8; 20];
+    backer_lock_hash.copy_from_slice(&backer_lock_script.calc_script_hash().raw_data()[0..20]);
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let input_lock_script = context
+        .build_script(
+            &always_success_out_point,
+            Bytes::from(vec![input_lock_args]),
+        )
+        .expect("input lock script");
+    let input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(input_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let input = CellInput::new_builder()
+        .previous_output(input_out_point)
+        .build();
+    let vote_output = CellOutput::new_builder()
+        .capacity((100 * SHANNONS_PER_CKB).pack())
+        .lock(backer_lock_script)
+        .type_(Some(vote_type_script).pack())
+        .build();
+    let outputs = data(&backer_lock_hash)
+        .into_iter()
+        .map(|data| (vote_output.clone(), data))
+        .collect();
+    let (_, sender_key, _) = prepare_sender_key();
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        vec![],
+        Bytes::new(),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_vote_create_success() {
+    let mut context = Context::default();
+    let tx = build_vote_tx(&mut context, 0);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_vote_create_without_backer_failed() {
+    let mut context = Context::default();
+    let tx = build_vote_tx(&mut context, 1);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_NOT_AUTHORIZED);
+}
+
+#[test]
+fn test_delegation_create_success() {
+    let mut context = Context::default();
+    let tx = build_vote_tx_of_data(&mut context, 0, |backer_lock_hash| {
+        build_delegation(backer_lock_hash, &[7u8; 20])
+    });
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_delegation_create_without_delegator_failed() {
+    let mut context = Context::default();
+    let tx = build_vote_tx_of_data(&mut context, 1, |backer_lock_hash| {
+        build_delegation(backer_lock_hash, &[7u8; 20])
+    });
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_NOT_AUTHORIZED);
+}
+
+#[test]
+fn test_delegation_create_to_itself_failed() {
+    let mut context = Context::default();
+    let tx = build_vote_tx_of_data(&mut context, 0, |backer_lock_hash| {
+        build_delegation(backer_lock_hash, backer_lock_hash)
+    });
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_INVALID_DATA);
+}
+
+// deploy the crowdfunding lock of a project counting the votes, return it with the vote type script
+fn prepare_voting_lock_script(context: &mut Context) -> (Script, Script, Vec<CellDep>, Script) {
+    let (vote_out_point, vote_type) = deploy_vote_type_script(context);
+    let (lock_script, state_type_script, cell_deps) = prepare_lock_script_of_project(
+        context,
+        &build_project_data_v2(&[(FIELD_VOTE_TYPE, vote_type)]),
+    );
+    let vote_type_script = build_vote_type_script(context, &vote_out_point, &lock_script);
+    (lock_script, state_type_script, cell_deps, vote_type_script)
+}
+
+fn build_tally_lock_script(lock_script: &Script, milestone: u8) -> Script {
+    let escrow_lock_script = build_escrow_lock_script(lock_script, milestone);
+    let mut args = escrow_lock_script.args().raw_data().to_vec();
+    args.push(0);
+    escrow_lock_script
+        .as_builder()
+        .args(Bytes::from(args).pack())
+        .build()
+}
+
+// backer 20 bytes, vote 1 byte, weight 16 bytes, signature 65 bytes of the project id, the milestone and the vote,
+// next backer 20 bytes, zeros for the last leaf
+fn build_tally_leaf(
+    key: &str,
+    lock_script: &Script,
+    milestone: u8,
+    backer: &[u8; 20],
+    weight: u64,
+) -> Vec<u8> {
+    let mut leaf = backer.to_vec();
+    leaf.push(1);
+    leaf.extend_from_slice(&(weight as u128).to_be_bytes());
+    let mut message = lock_script.args().raw_data()[0..32].to_vec();
+    message.push(milestone);
+    message.extend_from_slice(&leaf);
+    let message = H256::from(blake2b_256(&message));
+    let signature = Privkey::from_str(key)
+        .unwrap()
+        .sign_recoverable(&message)
+        .expect("sign");
+    leaf.extend_from_slice(&signature.serialize());
+    leaf.extend_from_slice(&[0u8; 20]);
+    leaf
+}
+
+// the tally of one leaf in favor, root hash 32 bytes, root sums of the votes in favor and of the votes cast
+// 16 bytes each, confirmed 1 byte
+fn build_tally(leaf: &[u8], weight: u64, confirmed: bool) -> Bytes {
+    let mut data = blake2b_256(leaf).to_vec();
+    data.extend_from_slice(&(weight as u128).to_be_bytes());
+    data.extend_from_slice(&(weight as u128).to_be_bytes());
+    data.push(confirmed as u8);
+    Bytes::from(data)
+}
+
+fn build_tally_output(lock_script: &Script, vote_type_script: &Script) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity((200 * SHANNONS_PER_CKB).pack())
+        .lock(build_tally_lock_script(lock_script, 0))
+        .type_(Some(vote_type_script.clone()).pack())
+        .build()
+}
+
+// The creator submits the tally of the vote of the backer on the first milestone,
+// and the position of the backer with 200 CKB pledged is in the cell deps,
+// with the position of another backer with 100 CKB pledged and its delegations to the backer if any
+fn build_tally_submit_tx(
+    context: &mut Context,
+    weight: u64,
+    delegations: usize,
+) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps, vote_type_script) =
+        prepare_voting_lock_script(context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+    let (_, sender_key, sender_lock_hash) = prepare_sender_key();
+    cell_deps.push(build_state_dep(
+        context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let position_out_point = create_funding_cell(
+        context,
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(lock_script.clone())
+            .build(),
+        build_position_data(200 * SHANNONS_PER_CKB),
+    );
+    cell_deps.push(CellDep::new_builder().out_point(position_out_point).build());
+    if delegations > 0 {
+        let delegator = [7u8; 20];
+        let position_out_point = create_funding_cell(
+            context,
+            CellOutput::new_builder()
+                .capacity((50 * SHANNONS_PER_CKB).pack())
+                .lock(replace_lock_args(&lock_script, 52, &delegator))
+                .build(),
+            build_position_data(100 * SHANNONS_PER_CKB),
+        );
+        cell_deps.push(CellDep::new_builder().out_point(position_out_point).build());
+        let delegator_lock_script = prepare_creator_lock_script(context);
+        for _ in 0..delegations {
+            let delegation_out_point = context.create_cell(
+                CellOutput::new_builder()
+                    .capacity((100 * SHANNONS_PER_CKB).pack())
+                    .lock(delegator_lock_script.clone())
+                    .type_(Some(vote_type_script.clone()).pack())
+                    .build(),
+                build_delegation(&delegator, &sender_lock_hash),
+            );
+            cell_deps.push(
+                CellDep::new_builder()
+                    .out_point(delegation_out_point)
+                    .build(),
+            );
+        }
+    }
+
+    let creator_lock_script = prepare_creator_lock_script(context);
+    let input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(creator_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let input = CellInput::new_builder()
+        .previous_output(input_out_point)
+        .build();
+    let leaf = build_tally_leaf(&sender_key, &lock_script, 0, &sender_lock_hash, weight);
+    let outputs = vec![(
+        build_tally_output(&lock_script, &vote_type_script),
+        build_tally(&leaf, weight, false),
+    )];
+    let witness = WitnessArgs::new_builder()
+        .output_type(Some(Bytes::from(leaf)).pack())
+        .build()
+        .as_bytes();
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        cell_deps,
+        witness,
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_vote_submit_tally_success() {
+    let mut context = Context::default();
+    let tx = build_tally_submit_tx(&mut context, 200 * SHANNONS_PER_CKB, 0);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_vote_submit_tally_over_pledge_failed() {
+    let mut context = Context::default();
+    let tx = build_tally_submit_tx(&mut context, 300 * SHANNONS_PER_CKB, 0);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_TALLY_WEIGHT_WRONG);
+}
+
+#[test]
+fn test_vote_submit_tally_with_delegated_weight_success() {
+    let mut context = Context::default();
+    let tx = build_tally_submit_tx(&mut context, 300 * SHANNONS_PER_CKB, 1);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_vote_submit_tally_with_duplicated_delegations_failed() {
+    let mut context = Context::default();
+    let tx = build_tally_submit_tx(&mut context, 300 * SHANNONS_PER_CKB, 2);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_DELEGATION_DUPLICATED);
+}
+
+// the creator confirms the tally submitted the epochs ago
+fn build_tally_confirm_tx(context: &mut Context, epochs: u64) -> TransactionView {
+    let (lock_script, _, cell_deps, vote_type_script) = prepare_voting_lock_script(context);
+    let (_, receiver_key, _) = parepare_receiver_key();
+    let (_, sender_key, sender_lock_hash) = prepare_sender_key();
+    let leaf = build_tally_leaf(
+        &sender_key,
+        &lock_script,
+        0,
+        &sender_lock_hash,
+        200 * SHANNONS_PER_CKB,
+    );
+    let tally_output = build_tally_output(&lock_script, &vote_type_script);
+    let tally_out_point = context.create_cell(
+        tally_output.clone(),
+        build_tally(&leaf, 200 * SHANNONS_PER_CKB, false),
+    );
+    let input = CellInput::new_builder()
+        .previous_output(tally_out_point)
+        .since(relative_epoch_since(epochs).pack())
+        .build();
+    let outputs = vec![(
+        tally_output,
+        build_tally(&leaf, 200 * SHANNONS_PER_CKB, true),
+    )];
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_receiver_confirm_tally_success() {
+    let mut context = Context::default();
+    let tx = build_tally_confirm_tx(&mut context, 18);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_confirm_tally_within_challenge_epochs_failed() {
+    let mut context = Context::default();
+    let tx = build_tally_confirm_tx(&mut context, 10);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_TALLY_NOT_CONFIRMABLE);
+}
+
+// anyone takes the tally with the proof of the only leaf, signed by the key for the backer
+fn build_tally_challenge_tx(context: &mut Context, key: &str) -> TransactionView {
+    let (lock_script, _, cell_deps, vote_type_script) = prepare_voting_lock_script(context);
+    let (_, sender_key, sender_lock_hash) = prepare_sender_key();
+    let leaf = build_tally_leaf(
+        key,
+        &lock_script,
+        0,
+        &sender_lock_hash,
+        200 * SHANNONS_PER_CKB,
+    );
+    let tally_output = build_tally_output(&lock_script, &vote_type_script);
+    let tally_out_point = context.create_cell(
+        tally_output,
+        build_tally(&leaf, 200 * SHANNONS_PER_CKB, false),
+    );
+    let input = CellInput::new_builder()
+        .previous_output(tally_out_point)
+        .build();
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(prepare_creator_lock_script(context))
+            .build(),
+        Bytes::new(),
+    )];
+    let mut challenge = vec![CHALLENGE_WRONG_SIGNATURE];
+    challenge.extend_from_slice(&leaf);
+    challenge.push(0);
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        cell_deps,
+        build_action_witness(ACTION_CHALLENGE, &challenge),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_challenge_tally_leaf_not_signed_by_backer_success() {
+    let mut context = Context::default();
+    let (_, receiver_key, _) = parepare_receiver_key();
+    let tx = build_tally_challenge_tx(&mut context, &receiver_key);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_challenge_tally_leaf_signed_by_backer_failed() {
+    let mut context = Context::default();
+    let (_, sender_key, _) = prepare_sender_key();
+    let tx = build_tally_challenge_tx(&mut context, &sender_key);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_TALLY_NOT_FRAUDULENT);
+}
+
+// the tally of the vote in favor of the backer with 200 CKB out of 300 CKB is confirmed
+fn build_tallied_escrow_release_tx(context: &mut Context, quorum: u8) -> TransactionView {
+    let (vote_out_point, vote_type) = deploy_vote_type_script(context);
+    let project_data =
+        build_project_data_v2(&[(FIELD_VOTE_TYPE, vote_type), (FIELD_QUORUMS, vec![quorum])]);
+    build_escrow_release_tx_of_project(context, &project_data, 20, |context, lock_script| {
+        let (_, sender_key, sender_lock_hash) = prepare_sender_key();
+        let vote_type_script = build_vote_type_script(context, &vote_out_point, lock_script);
+        let leaf = build_tally_leaf(
+            &sender_key,
+            lock_script,
+            0,
+            &sender_lock_hash,
+            200 * SHANNONS_PER_CKB,
+        );
+        let tally_output = build_tally_output(lock_script, &vote_type_script);
+        let tally_out_point = context.create_cell(
+            tally_output,
+            build_tally(&leaf, 200 * SHANNONS_PER_CKB, true),
+        );
+        vec![CellDep::new_builder().out_point(tally_out_point).build()]
+    })
+}
+
+#[test]
+fn test_receiver_release_escrow_with_confirmed_tally_success() {
+    let mut context = Context::default();
+    let tx = build_tallied_escrow_release_tx(&mut context, 6);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_release_escrow_with_confirmed_tally_quorum_not_reached_failed() {
+    let mut context = Context::default();
+    // all the votes cast are in favor, but they are only 200 CKB out of 300 CKB
+    let tx = build_tallied_escrow_release_tx(&mut context, 7);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_QUORUM_NOT_REACHED);
+}
+
+// Anyone takes the tally with the vote signed by the key for the voter and the proof of the only leaf,
+// which is the vote of the backer, and the position of the voter with 100 CKB pledged is in the cell deps
+fn build_tally_left_out_challenge_tx(
+    context: &mut Context,
+    key: &str,
+    voter: &[u8; 20],
+) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps, vote_type_script) =
+        prepare_voting_lock_script(context);
+    let (_, sender_key, sender_lock_hash) = prepare_sender_key();
+    cell_deps.push(build_state_dep(
+        context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let position_out_point = create_funding_cell(
+        context,
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(replace_lock_args(&lock_script, 52, voter))
+            .build(),
+        build_position_data(100 * SHANNONS_PER_CKB),
+    );
+    cell_deps.push(CellDep::new_builder().out_point(position_out_point).build());
+
+    let leaf = build_tally_leaf(
+        &sender_key,
+        &lock_script,
+        0,
+        &sender_lock_hash,
+        200 * SHANNONS_PER_CKB,
+    );
+    let tally_output = build_tally_output(&lock_script, &vote_type_script);
+    let tally_out_point = context.create_cell(
+        tally_output,
+        build_tally(&leaf, 200 * SHANNONS_PER_CKB, false),
+    );
+    let input = CellInput::new_builder()
+        .previous_output(tally_out_point)
+        .build();
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(prepare_creator_lock_script(context))
+            .build(),
+        Bytes::new(),
+    )];
+    let mut challenge = vec![CHALLENGE_VOTE_LEFT_OUT];
+    challenge.extend_from_slice(&build_tally_leaf(
+        key,
+        &lock_script,
+        0,
+        voter,
+        100 * SHANNONS_PER_CKB,
+    ));
+    challenge.extend_from_slice(&leaf);
+    challenge.push(0);
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        cell_deps,
+        build_action_witness(ACTION_CHALLENGE, &challenge),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_challenge_tally_vote_left_out_success() {
+    let mut context = Context::default();
+    let (_, receiver_key, receiver_lock_hash) = parepare_receiver_key();
+    let tx = build_tally_left_out_challenge_tx(&mut context, &receiver_key, &receiver_lock_hash);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_challenge_tally_vote_in_tree_failed() {
+    let mut context = Context::default();
+    let (_, sender_key, sender_lock_hash) = prepare_sender_key();
+    let tx = build_tally_left_out_challenge_tx(&mut context, &sender_key, &sender_lock_hash);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_TALLY_NOT_FRAUDULENT);
+}
+
+// the sibling of the leaf in the proof: side 1 byte, hash 32 bytes, sums of the votes in favor and cast 16 bytes each
+fn build_sum_sibling(side: u8, leaf: &[u8], weight: u64) -> Vec<u8> {
+    let mut sibling = vec![side];
+    sibling.extend_from_slice(&blake2b_256(leaf));
+    sibling.extend_from_slice(&(weight as u128).to_be_bytes());
+    sibling.extend_from_slice(&(weight as u128).to_be_bytes());
+    sibling
+}
+
+// The tally of the vote of the backer weighing 250 CKB and the vote of another backer with 100 CKB pledged,
+// out of 300 CKB, and anyone takes it with the proof of the leaf of the backer,
+// followed by the proof of the other leaf if proven, with the position of the other backer in the cell deps
+fn build_tally_weight_challenge_tx(context: &mut Context, other_proven: bool) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps, vote_type_script) =
+        prepare_voting_lock_script(context);
+    let (_, sender_key, sender_lock_hash) = prepare_sender_key();
+    let (_, receiver_key, receiver_lock_hash) = parepare_receiver_key();
+    cell_deps.push(build_state_dep(
+        context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+    ));
+    let position_out_point = create_funding_cell(
+        context,
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(replace_lock_args(&lock_script, 52, &receiver_lock_hash))
+            .build(),
+        build_position_data(100 * SHANNONS_PER_CKB),
+    );
+    cell_deps.push(CellDep::new_builder().out_point(position_out_point).build());
+
+    // the leaf of the backer comes first and points to the other one
+    let mut leaf = build_tally_leaf(
+        &sender_key,
+        &lock_script,
+        0,
+        &sender_lock_hash,
+        250 * SHANNONS_PER_CKB,
+    );
+    leaf[102..122].copy_from_slice(&receiver_lock_hash);
+    let other_leaf = build_tally_leaf(
+        &receiver_key,
+        &lock_script,
+        0,
+        &receiver_lock_hash,
+        100 * SHANNONS_PER_CKB,
+    );
+    let leaf_sibling = build_sum_sibling(1, &other_leaf, 100 * SHANNONS_PER_CKB);
+    let other_sibling = build_sum_sibling(0, &leaf, 250 * SHANNONS_PER_CKB);
+    let mut nodes = other_sibling[1..].to_vec();
+    nodes.extend_from_slice(&leaf_sibling[1..]);
+    let mut tally = blake2b_256(&nodes).to_vec();
+    tally.extend_from_slice(&(350 * SHANNONS_PER_CKB as u128).to_be_bytes());
+    tally.extend_from_slice(&(350 * SHANNONS_PER_CKB as u128).to_be_bytes());
+    tally.push(0);
+    let tally_out_point = context.create_cell(
+        build_tally_output(&lock_script, &vote_type_script),
+        Bytes::from(tally),
+    );
+    let input = CellInput::new_builder()
+        .previous_output(tally_out_point)
+        .build();
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(prepare_creator_lock_script(context))
+            .build(),
+        Bytes::new(),
+    )];
+    let mut challenge = vec![CHALLENGE_WEIGHT_TOO_HIGH];
+    challenge.extend_from_slice(&leaf);
+    challenge.push(1);
+    challenge.extend_from_slice(&leaf_sibling);
+    if other_proven {
+        challenge.extend_from_slice(&other_leaf);
+        challenge.push(1);
+        challenge.extend_from_slice(&other_sibling);
+    }
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        cell_deps,
+        build_action_witness(ACTION_CHALLENGE, &challenge),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_challenge_tally_leaf_weight_too_high_success() {
+    let mut context = Context::default();
+    // the backer weighs no more than the 200 CKB not pledged by the other backer
+    let tx = build_tally_weight_challenge_tx(&mut context, true);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_challenge_tally_leaf_weight_without_other_leaves_failed() {
+    let mut context = Context::default();
+    // the leaf alone could weigh the 300 CKB of all the positions with the delegations
+    let tx = build_tally_weight_challenge_tx(&mut context, false);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_TALLY_NOT_FRAUDULENT);
+}
+
+// escrow data with objections: finalize tx hash 32 bytes, objected weight 16 bytes, backers 20 bytes each
+fn build_objections(finalize_tx_hash: &[u8], objected: u64, backers: &[[u8; 20]]) -> Bytes {
+    let mut data = finalize_tx_hash.to_vec();
+    data.extend_from_slice(&(objected as u128).to_be_bytes());
+    for backer in backers {
+        data.extend_from_slice(backer);
+    }
+    Bytes::from(data)
+}
+
+// The backer has objected to the first milestone with the weight out of 300 CKB in the veto mode of 30%,
+// and the creator releases its escrow cell since the epoch
+fn build_vetoed_escrow_release_tx(
+    context: &mut Context,
+    objected: u64,
+    epoch: u64,
+) -> TransactionView {
+    let (_, vote_type) = deploy_vote_type_script(context);
+    let project_data = build_project_data_v2(&[
+        (FIELD_VOTE_TYPE, vote_type),
+        (FIELD_VETO_THRESHOLD, vec![3]),
+    ]);
+    let (_, _, sender_lock_hash) = prepare_sender_key();
+    build_escrow_release_tx_of_escrow(
+        context,
+        &project_data,
+        (20, None),
+        (
+            build_objections(&[0u8; 32], objected, &[sender_lock_hash]),
+            absolute_epoch_since(epoch),
+        ),
+        Bytes::new(),
+        |_, _| vec![],
+    )
+}
+
+#[test]
+fn test_receiver_release_escrow_not_vetoed_success() {
+    let mut context = Context::default();
+    let tx = build_vetoed_escrow_release_tx(&mut context, 50 * SHANNONS_PER_CKB, 38);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_release_escrow_vetoed_failed() {
+    let mut context = Context::default();
+    let tx = build_vetoed_escrow_release_tx(&mut context, 100 * SHANNONS_PER_CKB, 38);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_VETOED);
+}
+
+#[test]
+fn test_receiver_release_escrow_within_veto_window_failed() {
+    let mut context = Context::default();
+    // the veto window of the milestone at the epoch 20 ends at the epoch 38
+    let tx = build_vetoed_escrow_release_tx(&mut context, 50 * SHANNONS_PER_CKB, 30);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RELEASE_TOO_EARLY);
+}
+
+// Anyone counts the vote of the backer on the first milestone cast at the epoch into its escrow cell
+// since the epoch, and the position of the backer with 100 CKB pledged is in the cell deps
+fn build_object_tx(
+    context: &mut Context,
+    approve: bool,
+    voted_epoch: u64,
+    since_epoch: u64,
+) -> TransactionView {
+    let (vote_out_point, vote_type) = deploy_vote_type_script(context);
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script_of_project(
+        context,
+        &build_project_data_v2(&[
+            (FIELD_VOTE_TYPE, vote_type),
+            (FIELD_VETO_THRESHOLD, vec![3]),
+        ]),
+    );
+    let (_, sender_key, sender_lock_hash) = prepare_sender_key();
+    cell_deps.push(build_state_dep(
+        context,
+        &state_type_script,
+        build_funded_state(
+            PHASE_MILESTONE,
+            0,
+            300 * SHANNONS_PER_CKB,
+            150 * SHANNONS_PER_CKB,
+            None,
+        ),
+    ));
+    let vote_type_script = build_vote_type_script(context, &vote_out_point, &lock_script);
+    let voter_lock_script = prepare_creator_lock_script(context);
+    let vote_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((100 * SHANNONS_PER_CKB).pack())
+            .lock(voter_lock_script)
+            .type_(Some(vote_type_script).pack())
+            .build(),
+        build_vote(0, &sender_lock_hash, approve, 100 * SHANNONS_PER_CKB),
+    );
+    let header = HeaderBuilder::default()
+        .epoch(EpochNumberWithFraction::new(voted_epoch, 0, 1).pack())
+        .number((voted_epoch * 1000).pack())
+        .build();
+    context.insert_header(header.clone());
+    context.link_cell_with_block(vote_out_point.clone(), header.hash(), 0);
+    cell_deps.push(CellDep::new_builder().out_point(vote_out_point).build());
+    let position_out_point = create_funding_cell(
+        context,
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(lock_script.clone())
+            .build(),
+        build_position_data(100 * SHANNONS_PER_CKB),
+    );
+    cell_deps.push(CellDep::new_builder().out_point(position_out_point).build());
+
+    let escrow_output = CellOutput::new_builder()
+        .capacity((50 * SHANNONS_PER_CKB).pack())
+        .lock(build_escrow_lock_script(&lock_script, 0))
+        .build();
+    let escrow_out_point = context.create_cell(escrow_output.clone(), Bytes::new());
+    let objections = build_objections(
+        escrow_out_point.tx_hash().as_slice(),
+        100 * SHANNONS_PER_CKB,
+        &[sender_lock_hash],
+    );
+    let input = CellInput::new_builder()
+        .previous_output(escrow_out_point)
+        .since(absolute_epoch_since(since_epoch).pack())
+        .build();
+    let tx = build_tx(
+        context,
+        vec![input],
+        vec![(escrow_output, objections)],
+        cell_deps,
+        build_action_witness(ACTION_OBJECT, &[]),
+        &sender_key,
+    );
+    sign_tx(
+        tx.as_advanced_builder().header_dep(header.hash()).build(),
+        &Privkey::from_str(&sender_key).unwrap(),
+    )
+}
+
+#[test]
+fn test_object_to_milestone_success() {
+    let mut context = Context::default();
+    let tx = build_object_tx(&mut context, false, 30, 30);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_object_to_milestone_with_vote_in_favor_failed() {
+    let mut context = Context::default();
+    let tx = build_object_tx(&mut context, true, 30, 30);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OBJECTIONS_WRONG);
+}
+
+#[test]
+fn test_object_to_milestone_after_veto_window_failed() {
+    let mut context = Context::default();
+    // the veto window of the milestone at the epoch 20 ends at the epoch 38
+    let tx = build_object_tx(&mut context, false, 30, 38);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OBJECTION_TOO_LATE);
+}
+
+#[test]
+fn test_object_to_milestone_with_vote_after_veto_window_failed() {
+    let mut context = Context::default();
+    let tx = build_object_tx(&mut context, false, 38, 30);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OBJECTIONS_WRONG);
+}
+
+fn pubkey_hash(key: &Privkey) -> [u8; 20] {
+    let mut pubkey_hash = [0u8; 20];
+    pubkey_hash.copy_from_slice(&blake2b_256(key.pubkey().unwrap().serialize())[0..20]);
+    pubkey_hash
+}
+
+// The council of the issuer, the sender and a third member approves with 2 signatures,
+// and the keys sign the first milestone, [milestone index 1 byte, signature 65 bytes] each
+fn build_council_escrow_release_tx(context: &mut Context, keys: &[Privkey]) -> TransactionView {
+    let (_, issuer_pubkey_hash) = prepare_issuer_key();
+    let (_, sender_key, _) = prepare_sender_key();
+    let sender_key = Privkey::from_str(&sender_key).unwrap();
+    let mut council = vec![2];
+    council.extend_from_slice(&issuer_pubkey_hash);
+    council.extend_from_slice(&pubkey_hash(&sender_key));
+    council.extend_from_slice(&[7u8; 20]);
+    let project_data = build_project_data_v2(&[(FIELD_COUNCIL, council)]);
+
+    let mut message = blake2b_256(&project_data).to_vec();
+    message.push(0);
+    let message = H256::from(blake2b_256(&message));
+    let mut signatures = Vec::new();
+    for key in keys {
+        signatures.push(0);
+        signatures.extend_from_slice(&key.sign_recoverable(&message).expect("sign").serialize());
+    }
+    let witness = WitnessArgs::new_builder()
+        .output_type(Some(Bytes::from(signatures)).pack())
+        .build()
+        .as_bytes();
+    build_escrow_release_tx_of_escrow(
+        context,
+        &project_data,
+        (20, None),
+        (Bytes::new(), 0),
+        witness,
+        |_, _| vec![],
+    )
+}
+
+#[test]
+fn test_receiver_release_escrow_approved_by_council_success() {
+    let mut context = Context::default();
+    let (issuer_key, _) = prepare_issuer_key();
+    let (_, sender_key, _) = prepare_sender_key();
+    let tx = build_council_escrow_release_tx(
+        &mut context,
+        &[issuer_key, Privkey::from_str(&sender_key).unwrap()],
+    );
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_release_escrow_below_council_threshold_failed() {
+    let mut context = Context::default();
+    let (issuer_key, _) = prepare_issuer_key();
+    let tx = build_council_escrow_release_tx(&mut context, &[issuer_key]);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_NOT_APPROVED_BY_COUNCIL);
+}
+
+#[test]
+fn test_receiver_release_escrow_signed_by_non_council_member_failed() {
+    let mut context = Context::default();
+    let (issuer_key, _) = prepare_issuer_key();
+    let (_, receiver_key, _) = parepare_receiver_key();
+    let tx = build_council_escrow_release_tx(
+        &mut context,
+        &[issuer_key, Privkey::from_str(&receiver_key).unwrap()],
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_COUNCIL_SIGNATURES_WRONG);
+}
+
+// The arbitrator decides on the escrow cell of the first milestone of 50 CKB since the epoch,
+// the release pays the only payee and the refund recreates the escrow cell with the refund record.
+// The backer with 200 CKB out of 300 CKB votes in favor of the milestone if approved.
+fn build_dispute_tx(
+    context: &mut Context,
+    decision: u8,
+    epoch: u64,
+    arbitrator_signed: bool,
+    approved: bool,
+) -> TransactionView {
+    let (vote_out_point, vote_type) = deploy_vote_type_script(context);
+    let (arbitrator_lock_script, arbitrator_lock_hash) = prepare_payout_lock_script(context, 9);
+    let (payee_lock_script, payee_lock_hash) = prepare_payout_lock_script(context, 1);
+    let mut payees = payee_lock_hash.to_vec();
+    payees.extend_from_slice(&1u16.to_be_bytes());
+    let project_data = build_project_data_v2(&[
+        (FIELD_VOTE_TYPE, vote_type),
+        (FIELD_ARBITRATOR, arbitrator_lock_hash.to_vec()),
+        (FIELD_PAYEES, payees),
+    ]);
+    let (lock_script, state_type_script, mut cell_deps) =
+        prepare_lock_script_of_project(context, &project_data);
+    if approved {
+        let (_, _, sender_lock_hash) = prepare_sender_key();
+        let vote_type_script = build_vote_type_script(context, &vote_out_point, &lock_script);
+        let voter_lock_script = prepare_creator_lock_script(context);
+        let vote_out_point = context.create_cell(
+            CellOutput::new_builder()
+                .capacity((100 * SHANNONS_PER_CKB).pack())
+                .lock(voter_lock_script)
+                .type_(Some(vote_type_script).pack())
+                .build(),
+            build_vote(0, &sender_lock_hash, true, 200 * SHANNONS_PER_CKB),
+        );
+        let position_out_point = create_funding_cell(
+            context,
+            CellOutput::new_builder()
+                .capacity((50 * SHANNONS_PER_CKB).pack())
+                .lock(lock_script.clone())
+                .build(),
+            build_position_data(200 * SHANNONS_PER_CKB),
+        );
+        cell_deps.push(CellDep::new_builder().out_point(vote_out_point).build());
+        cell_deps.push(CellDep::new_builder().out_point(position_out_point).build());
+    }
+    cell_deps.push(build_state_dep(
+        context,
+        &state_type_script,
+        build_funded_state(
+            PHASE_MILESTONE,
+            0,
+            300 * SHANNONS_PER_CKB,
+            150 * SHANNONS_PER_CKB,
+            None,
+        ),
+    ));
+
+    let escrow_output = CellOutput::new_builder()
+        .capacity((50 * SHANNONS_PER_CKB).pack())
+        .lock(build_escrow_lock_script(&lock_script, 0))
+        .build();
+    let escrow_out_point = context.create_cell(escrow_output.clone(), Bytes::new());
+    let mut refund_record = escrow_out_point.tx_hash().as_slice().to_vec();
+    refund_record.extend_from_slice(&(300 * SHANNONS_PER_CKB as u128).to_be_bytes());
+    let mut inputs = vec![CellInput::new_builder()
+        .previous_output(escrow_out_point)
+        .since(absolute_epoch_since(epoch).pack())
+        .build()];
+    let input_lock_script = if arbitrator_signed {
+        arbitrator_lock_script
+    } else {
+        prepare_creator_lock_script(context)
+    };
+    let input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((100 * SHANNONS_PER_CKB).pack())
+            .lock(input_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    inputs.push(
+        CellInput::new_builder()
+            .previous_output(input_out_point)
+            .build(),
+    );
+    let outputs = match decision {
+        DECISION_RELEASE => vec![(
+            CellOutput::new_builder()
+                .capacity((50 * SHANNONS_PER_CKB).pack())
+                .lock(payee_lock_script)
+                .build(),
+            Bytes::new(),
+        )],
+        _ => vec![(escrow_output, Bytes::from(refund_record))],
+    };
+    let (_, receiver_key, _) = parepare_receiver_key();
+    build_tx(
+        context,
+        inputs,
+        outputs,
+        cell_deps,
+        build_action_witness(ACTION_DISPUTE, &[decision]),
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_arbitrator_release_disputed_escrow_success() {
+    let mut context = Context::default();
+    let tx = build_dispute_tx(&mut context, DECISION_RELEASE, 38, true, false);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_arbitrator_refund_disputed_escrow_success() {
+    let mut context = Context::default();
+    let tx = build_dispute_tx(&mut context, DECISION_REFUND, 38, true, false);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_arbitrator_dispute_within_arbitration_epochs_failed() {
+    let mut context = Context::default();
+    // the arbitration of the milestone at the epoch 20 starts at the epoch 38
+    let tx = build_dispute_tx(&mut context, DECISION_RELEASE, 30, true, false);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RELEASE_TOO_EARLY);
+}
+
+#[test]
+fn test_arbitrator_dispute_approved_milestone_failed() {
+    let mut context = Context::default();
+    // the backers have approved the milestone, which is the creator's to release
+    let tx = build_dispute_tx(&mut context, DECISION_REFUND, 38, true, true);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_APPROVED);
+}
+
+#[test]
+fn test_dispute_without_arbitrator_failed() {
+    let mut context = Context::default();
+    let tx = build_dispute_tx(&mut context, DECISION_REFUND, 38, false, false);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_ARBITRATOR_NOT_SIGNED);
+}
+
+#[test]
+fn test_receiver_quadratic_funding_success() {
+    let mut context = Context::default();
+    // the only backer pledges 150 CKB, whose integer square root in shannons is 122474
+    let tx = build_v2_funding_tx_of_weight(
+        &mut context,
+        &[
+            (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+            (FIELD_QUADRATIC, vec![1]),
+        ],
+        Bytes::new(),
+        vec![],
+        (10, Some(122474)),
+    );
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_quadratic_funding_total_weight_wrong_failed() {
+    let mut context = Context::default();
+    let tx = build_v2_funding_tx_of_weight(
+        &mut context,
+        &[
+            (FIELD_MODE, vec![MODE_KEEP_IT_ALL]),
+            (FIELD_QUADRATIC, vec![1]),
+        ],
+        Bytes::new(),
+        vec![],
+        (10, Some(150 * SHANNONS_PER_CKB)),
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_TOTAL_WEIGHT_WRONG);
+}
+
+// The backers of 200 CKB and 100 CKB weigh 141421 and 100000 in the quadratic voting,
+// the first votes in favor of the first milestone and so does the second if it votes
+fn build_quadratic_escrow_release_tx(context: &mut Context, both_voted: bool) -> TransactionView {
+    let (vote_out_point, vote_type) = deploy_vote_type_script(context);
+    let project_data =
+        build_project_data_v2(&[(FIELD_VOTE_TYPE, vote_type), (FIELD_QUADRATIC, vec![1])]);
+    build_escrow_release_tx_of_escrow(
+        context,
+        &project_data,
+        (20, Some(241421)),
+        (Bytes::new(), 0),
+        Bytes::new(),
+        |context, lock_script| {
+            let (_, _, sender_lock_hash) = prepare_sender_key();
+            let other_backer = [7u8; 20];
+            let vote_type_script = build_vote_type_script(context, &vote_out_point, lock_script);
+            let mut votes = vec![(sender_lock_hash, 200 * SHANNONS_PER_CKB)];
+            if both_voted {
+                votes.push((other_backer, 100 * SHANNONS_PER_CKB));
+            }
+            let voter_lock_script = prepare_creator_lock_script(context);
+            let mut cell_deps = Vec::new();
+            for (backer, pledged) in votes {
+                let vote_out_point = context.create_cell(
+                    CellOutput::new_builder()
+                        .capacity((100 * SHANNONS_PER_CKB).pack())
+                        .lock(voter_lock_script.clone())
+                        .type_(Some(vote_type_script.clone()).pack())
+                        .build(),
+                    build_vote(0, &backer, true, pledged),
+                );
+                cell_deps.push(CellDep::new_builder().out_point(vote_out_point).build());
+            }
+            for (backer, pledged) in [
+                (sender_lock_hash, 200 * SHANNONS_PER_CKB),
+                (other_backer, 100 * SHANNONS_PER_CKB),
+            ]
+            .iter()
+            {
+                let position_out_point = create_funding_cell(
+                    context,
+                    CellOutput::new_builder()
+                        .capacity((50 * SHANNONS_PER_CKB).pack())
+                        .lock(replace_lock_args(lock_script, 52, backer))
+                        .build(),
+                    build_position_data(*pledged),
+                );
+                cell_deps.push(CellDep::new_builder().out_point(position_out_point).build());
+            }
+            cell_deps
+        },
+    )
+}
+
+#[test]
+fn test_receiver_release_escrow_quadratic_votes_success() {
+    let mut context = Context::default();
+    let tx = build_quadratic_escrow_release_tx(&mut context, true);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_release_escrow_quadratic_whale_vote_failed() {
+    let mut context = Context::default();
+    // 2/3 of the pledges but only 141421 out of 241421 in the quadratic weight, below 60%
+    let tx = build_quadratic_escrow_release_tx(&mut context, false);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_NOT_APPROVED);
+}
+
+// The backer with 100 CKB pledged votes in favor of the first milestone,
+// and another backer with 100 CKB pledged delegates to it and may vote by itself
+fn build_delegated_escrow_release_tx(
+    context: &mut Context,
+    delegator_vote: Option<bool>,
+) -> TransactionView {
+    build_delegated_escrow_release_tx_of_delegates(context, delegator_vote, None)
+}
+
+// the delegator may delegate to another delegate too
+fn build_delegated_escrow_release_tx_of_delegates(
+    context: &mut Context,
+    delegator_vote: Option<bool>,
+    other_delegate: Option<[u8; 20]>,
+) -> TransactionView {
+    let (vote_out_point, vote_type) = deploy_vote_type_script(context);
+    let project_data = build_project_data_v2(&[(FIELD_VOTE_TYPE, vote_type)]);
+    build_escrow_release_tx_of_project(context, &project_data, 20, |context, lock_script| {
+        let (_, _, sender_lock_hash) = prepare_sender_key();
+        let delegator = [7u8; 20];
+        let vote_type_script = build_vote_type_script(context, &vote_out_point, lock_script);
+        let voter_lock_script = prepare_creator_lock_script(context);
+        let mut vote_cells = vec![
+            build_vote(0, &sender_lock_hash, true, 100 * SHANNONS_PER_CKB),
+            build_delegation(&delegator, &sender_lock_hash),
+        ];
+        if let Some(approve) = delegator_vote {
+            vote_cells.push(build_vote(0, &delegator, approve, 100 * SHANNONS_PER_CKB));
+        }
+        if let Some(other_delegate) = other_delegate {
+            vote_cells.push(build_delegation(&delegator, &other_delegate));
+        }
+        let mut cell_deps = Vec::new();
+        for data in vote_cells {
+            let out_point = context.create_cell(
+                CellOutput::new_builder()
+                    .capacity((100 * SHANNONS_PER_CKB).pack())
+                    .lock(voter_lock_script.clone())
+                    .type_(Some(vote_type_script.clone()).pack())
+                    .build(),
+                data,
+            );
+            cell_deps.push(CellDep::new_builder().out_point(out_point).build());
+        }
+        for backer in [sender_lock_hash, delegator].iter() {
+            let position_out_point = create_funding_cell(
+                context,
+                CellOutput::new_builder()
+                    .capacity((50 * SHANNONS_PER_CKB).pack())
+                    .lock(replace_lock_args(lock_script, 52, backer))
+                    .build(),
+                build_position_data(100 * SHANNONS_PER_CKB),
+            );
+            cell_deps.push(CellDep::new_builder().out_point(position_out_point).build());
+        }
+        cell_deps
+    })
+}
+
+#[test]
+fn test_receiver_release_escrow_with_delegated_votes_success() {
+    let mut context = Context::default();
+    // 200 CKB out of 300 CKB in favor with the delegated votes
+    let tx = build_delegated_escrow_release_tx(&mut context, None);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_release_escrow_delegator_voted_against_failed() {
+    let mut context = Context::default();
+    // the delegator votes against by itself, so only 100 CKB out of 300 CKB are in favor
+    let tx = build_delegated_escrow_release_tx(&mut context, Some(false));
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_NOT_APPROVED);
+}
+
+#[test]
+fn test_receiver_release_escrow_with_conflicting_delegations_failed() {
+    let mut context = Context::default();
+    // the delegator delegates to another backer too, which is not counted over the first delegation
+    let tx = build_delegated_escrow_release_tx_of_delegates(&mut context, None, Some([8u8; 20]));
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_DELEGATION_DUPLICATED);
+}
+
+// the salt the backer commits to the vote with
+const VOTE_SALT: [u8; 32] = [9; 32];
+
+// milestone index 1 byte, backer 20 bytes, commitment 32 bytes: blake2b of the project id 32 bytes,
+// the milestone index 1 byte, the backer 20 bytes, the vote 1 byte and the salt 32 bytes
+fn build_commitment(
+    project_id: &[u8],
+    milestone: u8,
+    backer: &[u8; 20],
+    approve: bool,
+    salt: &[u8; 32],
+) -> Bytes {
+    let mut data = vec![milestone];
+    data.extend_from_slice(backer);
+    let mut vote = project_id.to_vec();
+    vote.push(milestone);
+    vote.extend_from_slice(backer);
+    vote.push(approve as u8);
+    vote.extend_from_slice(salt);
+    data.extend_from_slice(&blake2b_256(&vote));
+    Bytes::from(data)
+}
+
+// the vote followed by the commitment it is revealed from
+fn build_reveal(
+    project_id: &[u8],
+    milestone: u8,
+    backer: &[u8; 20],
+    approve: bool,
+    weight: u64,
+) -> Bytes {
+    let mut data = build_vote(milestone, backer, approve, weight).to_vec();
+    data.extend_from_slice(
+        &build_commitment(project_id, milestone, backer, approve, &VOTE_SALT)[21..53],
+    );
+    Bytes::from(data)
+}
+
+// the backer commits to the votes on the milestones
+fn build_commit_tx(context: &mut Context, milestones: &[u8]) -> TransactionView {
+    let project_id = blake2b_256(PROJECT_DATA);
+    build_vote_tx_of_outputs(context, 0, |backer_lock_hash| {
+        milestones
+            .iter()
+            .map(|milestone| {
+                build_commitment(&project_id, *milestone, backer_lock_hash, true, &VOTE_SALT)
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn test_vote_commit_success() {
+    let mut context = Context::default();
+    let tx = build_commit_tx(&mut context, &[0]);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_vote_commit_to_milestones_success() {
+    let mut context = Context::default();
+    let tx = build_commit_tx(&mut context, &[0, 1]);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_vote_commit_twice_failed() {
+    let mut context = Context::default();
+    let tx = build_commit_tx(&mut context, &[0, 0]);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_COMMITMENT_DUPLICATED);
+}
+
+// The backer reveals the vote in favor of the first milestone at the epoch 20 with the salt,
+// from the commitment made in a block of the epoch, and the commitment is consumed since the epoch
+fn build_reveal_tx(
+    context: &mut Context,
+    committed_epoch: u64,
+    since_epoch: u64,
+    salt: [u8; 32],
+) -> TransactionView {
+    build_reveal_tx_of_commitments(context, committed_epoch, since_epoch, salt, 1)
+}
+
+// the same vote revealed from as many commitments of the backer
+fn build_reveal_tx_of_commitments(
+    context: &mut Context,
+    committed_epoch: u64,
+    since_epoch: u64,
+    salt: [u8; 32],
+    commitments: usize,
+) -> TransactionView {
+    let (vote_out_point, vote_type) = deploy_vote_type_script(context);
+    let project_data =
+        build_project_data_v2(&[(FIELD_VOTE_TYPE, vote_type), (FIELD_COMMIT_REVEAL, vec![1])]);
+    let (lock_script, _, cell_deps) = prepare_lock_script_of_project(context, &project_data);
+    let project_id = lock_script.args().raw_data()[0..32].to_vec();
+    let vote_type_script = build_vote_type_script(context, &vote_out_point, &lock_script);
+    let backer_lock_script = prepare_creator_lock_script(context);
+    let mut backer_lock_hash = [0u8; 20];
+    backer_lock_hash.copy_from_slice(&backer_lock_script.calc_script_hash().raw_data()[0..20]);
+    let vote_output = CellOutput::new_builder()
+        .capacity((200 * SHANNONS_PER_CKB).pack())
+        .lock(backer_lock_script)
+        .type_(Some(vote_type_script).pack())
+        .build();
+
+    let header = HeaderBuilder::default()
+        .epoch(EpochNumberWithFraction::new(committed_epoch, 0, 1).pack())
+        .number((committed_epoch * 1000).pack())
+        .build();
+    context.insert_header(header.clone());
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    for _ in 0..commitments {
+        let commitment_out_point = context.create_cell(
+            vote_output.clone(),
+            build_commitment(&project_id, 0, &backer_lock_hash, true, &VOTE_SALT),
+        );
+        context.link_cell_with_block(commitment_out_point.clone(), header.hash(), 0);
+        inputs.push(
+            CellInput::new_builder()
+                .previous_output(commitment_out_point)
+                .since(absolute_epoch_since(since_epoch).pack())
+                .build(),
+        );
+        outputs.push((
+            vote_output.clone(),
+            build_reveal(
+                &project_id,
+                0,
+                &backer_lock_hash,
+                true,
+                100 * SHANNONS_PER_CKB,
+            ),
+        ));
+    }
+    let witness = WitnessArgs::new_builder()
+        .output_type(Some(Bytes::from(salt.to_vec())).pack())
+        .build()
+        .as_bytes();
+    let (_, sender_key, _) = prepare_sender_key();
+    let tx = build_tx(context, inputs, outputs, cell_deps, witness, &sender_key);
+    tx.as_advanced_builder().header_dep(header.hash()).build()
+}
+
+#[test]
+fn test_vote_reveal_success() {
+    let mut context = Context::default();
+    let tx = build_reveal_tx(&mut context, 10, 20, VOTE_SALT);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_vote_reveal_twice_failed() {
+    let mut context = Context::default();
+    let tx = build_reveal_tx_of_commitments(&mut context, 10, 20, VOTE_SALT, 2);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_COMMITMENT_DUPLICATED);
+}
+
+#[test]
+fn test_vote_reveal_wrong_salt_failed() {
+    let mut context = Context::default();
+    let tx = build_reveal_tx(&mut context, 10, 20, [8; 32]);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_REVEAL_NOT_MATCHED);
+}
+
+#[test]
+fn test_vote_reveal_before_milestone_failed() {
+    let mut context = Context::default();
+    let tx = build_reveal_tx(&mut context, 10, 15, VOTE_SALT);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_REVEAL_TOO_EARLY);
+}
+
+#[test]
+fn test_vote_reveal_commitment_after_milestone_failed() {
+    let mut context = Context::default();
+    let tx = build_reveal_tx(&mut context, 20, 20, VOTE_SALT);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_VOTE_COMMITMENT_TOO_LATE);
+}
+
+// The backer votes in favor of the first milestone with 200 CKB out of 300 CKB in the commit-reveal voting,
+// by the vote revealed from its commitment at the epoch 25 or by a public vote, tallied since the epoch 38
+fn build_revealed_escrow_release_tx(context: &mut Context, revealed: bool) -> TransactionView {
+    build_revealed_escrow_release_tx_of_votes(context, &[(revealed, true, 25)], 38)
+}
+
+// The votes of the backer on the first milestone, each revealed or public, in favor or against,
+// and in a block of its revealed epoch, and the escrow is released since the epoch
+fn build_revealed_escrow_release_tx_of_votes(
+    context: &mut Context,
+    votes: &[(bool, bool, u64)],
+    since_epoch: u64,
+) -> TransactionView {
+    let (vote_out_point, vote_type) = deploy_vote_type_script(context);
+    let project_data =
+        build_project_data_v2(&[(FIELD_VOTE_TYPE, vote_type), (FIELD_COMMIT_REVEAL, vec![1])]);
+    let headers = votes
+        .iter()
+        .map(|(_, _, revealed_epoch)| {
+            let header = HeaderBuilder::default()
+                .epoch(EpochNumberWithFraction::new(*revealed_epoch, 0, 1).pack())
+                .number((revealed_epoch * 1000).pack())
+                .build();
+            context.insert_header(header.clone());
+            header
+        })
+        .collect::<Vec<_>>();
+    let tx = build_escrow_release_tx_of_escrow(
+        context,
+        &project_data,
+        (20, None),
+        (Bytes::new(), absolute_epoch_since(since_epoch)),
+        Bytes::new(),
+        |context, lock_script| {
+            let (_, _, sender_lock_hash) = prepare_sender_key();
+            let project_id = lock_script.args().raw_data()[0..32].to_vec();
+            let vote_type_script = build_vote_type_script(context, &vote_out_point, lock_script);
+            let voter_lock_script = prepare_creator_lock_script(context);
+            let mut cell_deps = Vec::new();
+            for ((revealed, approve, _), header) in votes.iter().zip(&headers) {
+                let vote = if *revealed {
+                    build_reveal(
+                        &project_id,
+                        0,
+                        &sender_lock_hash,
+                        *approve,
+                        200 * SHANNONS_PER_CKB,
+                    )
+                } else {
+                    build_vote(0, &sender_lock_hash, *approve, 200 * SHANNONS_PER_CKB)
+                };
+                let vote_out_point = context.create_cell(
+                    CellOutput::new_builder()
+                        .capacity((100 * SHANNONS_PER_CKB).pack())
+                        .lock(voter_lock_script.clone())
+                        .type_(Some(vote_type_script.clone()).pack())
+                        .build(),
+                    vote,
+                );
+                context.link_cell_with_block(vote_out_point.clone(), header.hash(), 0);
+                cell_deps.push(CellDep::new_builder().out_point(vote_out_point).build());
+            }
+            let position_out_point = create_funding_cell(
+                context,
+                CellOutput::new_builder()
+                    .capacity((50 * SHANNONS_PER_CKB).pack())
+                    .lock(lock_script.clone())
+                    .build(),
+                build_position_data(200 * SHANNONS_PER_CKB),
+            );
+            cell_deps.push(CellDep::new_builder().out_point(position_out_point).build());
+            cell_deps
+        },
+    );
+    let (_, receiver_key, _) = parepare_receiver_key();
+    sign_tx(
+        tx.as_advanced_builder()
+            .header_deps(headers.iter().map(|header| header.hash()))
+            .build(),
+        &Privkey::from_str(&receiver_key).unwrap(),
+    )
+}
+
+#[test]
+fn test_receiver_release_escrow_with_revealed_vote_success() {
+    let mut context = Context::default();
+    let tx = build_revealed_escrow_release_tx(&mut context, true);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_receiver_release_escrow_with_public_vote_in_commit_reveal_failed() {
+    let mut context = Context::default();
+    let tx = build_revealed_escrow_release_tx(&mut context, false);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_NOT_APPROVED);
+}
+
+#[test]
+fn test_receiver_release_escrow_with_vote_revealed_late_failed() {
+    let mut context = Context::default();
+    // the reveal window of the milestone at the epoch 20 closes at the epoch 38
+    let tx = build_revealed_escrow_release_tx_of_votes(&mut context, &[(true, true, 38)], 38);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_NOT_APPROVED);
+}
+
+#[test]
+fn test_receiver_release_escrow_before_reveal_window_closed_failed() {
+    let mut context = Context::default();
+    let tx = build_revealed_escrow_release_tx_of_votes(&mut context, &[(true, true, 25)], 37);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RELEASE_TOO_EARLY);
+}
+
+#[test]
+fn test_receiver_release_escrow_with_votes_revealed_twice_failed() {
+    let mut context = Context::default();
+    let tx = build_revealed_escrow_release_tx_of_votes(
+        &mut context,
+        &[(true, true, 25), (true, false, 25)],
+        38,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_COMMITMENT_DUPLICATED);
+}
+
+#[test]
+fn test_receiver_release_escrow_with_vote_revealed_again_after_window_failed() {
+    let mut context = Context::default();
+    // the backer committed twice in different transactions, and the vote revealed late is not skipped
+    let tx = build_revealed_escrow_release_tx_of_votes(
+        &mut context,
+        &[(true, false, 38), (true, true, 25)],
+        38,
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_COMMITMENT_DUPLICATED);
+}
+
+// the amendments follow the funded state: milestone index 1 byte, time 8 bytes, proposal 32 bytes
+fn build_amended_state(
+    phase: u8,
+    total_pledged: u64,
+    total_released: u64,
+    amendments: &[(u8, u64, &[u8])],
+) -> Bytes {
+    let mut data = build_funded_state(phase, 0, total_pledged, total_released, None).to_vec();
+    for (milestone, time, proposal) in amendments {
+        data.push(*milestone);
+        data.extend_from_slice(&time.to_be_bytes());
+        data.extend_from_slice(proposal);
+    }
+    Bytes::from(data)
+}
+
+fn build_amendment_lock_script(lock_script: &Script, milestone: u8) -> Script {
+    let escrow_lock_script = build_escrow_lock_script(lock_script, milestone);
+    let mut args = escrow_lock_script.args().raw_data().to_vec();
+    args.push(1);
+    escrow_lock_script
+        .as_builder()
+        .args(Bytes::from(args).pack())
+        .build()
+}
+
+// milestone index 1 byte, backer 20 bytes, vote 1 byte, weight 16 bytes, the new time of the milestone 8 bytes
+fn build_amendment_vote(
+    milestone: u8,
+    backer: &[u8; 20],
+    approve: bool,
+    weight: u64,
+    time: u64,
+) -> Bytes {
+    let mut data = build_vote(milestone, backer, approve, weight).to_vec();
+    data.extend_from_slice(&time.to_be_bytes());
+    Bytes::from(data)
+}
+
+// The creator proposes the new time of the first milestone (epoch 20) and the state records the amendment,
+// and the backer with 200 CKB out of 300 CKB votes on it with the position in the cell deps
+fn build_amendment_tx(
+    context: &mut Context,
+    proposed_time: u64,
+    recorded_time: u64,
+    approve: bool,
+) -> TransactionView {
+    let (vote_out_point, vote_type) = deploy_vote_type_script(context);
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script_of_project(
+        context,
+        &build_project_data_v2(&[(FIELD_VOTE_TYPE, vote_type)]),
+    );
+    let vote_type_script = build_vote_type_script(context, &vote_out_point, &lock_script);
+    let (_, _, sender_lock_hash) = prepare_sender_key();
+    let (_, receiver_key, _) = parepare_receiver_key();
+
+    let voter_lock_script = prepare_creator_lock_script(context);
+    let vote_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((100 * SHANNONS_PER_CKB).pack())
+            .lock(voter_lock_script)
+            .type_(Some(vote_type_script).pack())
+            .build(),
+        build_amendment_vote(
+            0,
+            &sender_lock_hash,
+            approve,
+            200 * SHANNONS_PER_CKB,
+            proposed_time,
+        ),
+    );
+    let position_out_point = create_funding_cell(
+        context,
+        CellOutput::new_builder()
+            .capacity((50 * SHANNONS_PER_CKB).pack())
+            .lock(lock_script.clone())
+            .build(),
+        build_position_data(200 * SHANNONS_PER_CKB),
+    );
+    cell_deps.push(CellDep::new_builder().out_point(vote_out_point).build());
+    cell_deps.push(CellDep::new_builder().out_point(position_out_point).build());
+
+    let proposal_lock_script = build_amendment_lock_script(&lock_script, 0);
+    let proposal_lock_hash = proposal_lock_script.calc_script_hash().raw_data();
+    let proposal_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity((100 * SHANNONS_PER_CKB).pack())
+            .lock(proposal_lock_script)
+            .build(),
+        Bytes::from(proposed_time.to_be_bytes().to_vec()),
+    );
+    let proposal_input = CellInput::new_builder()
+        .previous_output(proposal_out_point)
+        .build();
+    let state_input = build_state_input(
+        context,
+        &state_type_script,
+        build_state(
+            PHASE_FUNDED,
+            0,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+        ),
+        0,
+    );
+    let creator_lock_script = prepare_creator_lock_script(context);
+    let outputs = vec![
+        (
+            build_state_output(context, &state_type_script),
+            build_amended_state(
+                PHASE_FUNDED,
+                300 * SHANNONS_PER_CKB,
+                100 * SHANNONS_PER_CKB,
+                &[(0, recorded_time, &proposal_lock_hash)],
+            ),
+        ),
+        (
+            CellOutput::new_builder()
+                .capacity((100 * SHANNONS_PER_CKB).pack())
+                .lock(creator_lock_script)
+                .build(),
+            Bytes::new(),
+        ),
+    ];
+    build_tx(
+        context,
+        vec![proposal_input, state_input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &receiver_key,
+    )
+}
+
+#[test]
+fn test_creator_amend_milestone_success() {
+    let mut context = Context::default();
+    let tx = build_amendment_tx(&mut context, 25, 25, true);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_creator_amend_milestone_not_approved_failed() {
+    let mut context = Context::default();
+    let tx = build_amendment_tx(&mut context, 25, 25, false);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MILESTONE_NOT_APPROVED);
+}
+
+#[test]
+fn test_creator_amend_milestone_other_time_failed() {
+    let mut context = Context::default();
+    // the state records another time than the one the backers approved
+    let tx = build_amendment_tx(&mut context, 25, 26, true);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_AMENDMENT_WRONG);
+}
+
+#[test]
+fn test_creator_amend_milestone_earlier_failed() {
+    let mut context = Context::default();
+    // the milestone can only be moved later
+    let tx = build_amendment_tx(&mut context, 15, 15, true);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_STATE_INVALID_AMENDMENT);
+}
+
+// The backer refunds after the grace period of the first milestone (epoch 20),
+// which the state in the cell deps has amended to epoch 25 with a proposal of the lock or a forged one
+fn build_amended_refund_tx(context: &mut Context, forged: bool) -> TransactionView {
+    let (lock_script, state_type_script, mut cell_deps) = prepare_lock_script(context);
+    let (_, sender_key, _) = prepare_sender_key();
+
+    let proposal_lock_script = if forged {
+        prepare_creator_lock_script(context)
+    } else {
+        build_amendment_lock_script(&lock_script, 0)
+    };
+    cell_deps.push(build_state_dep(
+        context,
+        &state_type_script,
+        build_amended_state(
+            PHASE_FUNDED,
+            300 * SHANNONS_PER_CKB,
+            100 * SHANNONS_PER_CKB,
+            &[(0, 25, &proposal_lock_script.calc_script_hash().raw_data())],
+        ),
+    ));
+    let input = build_pledge_input(
+        context,
+        &lock_script,
+        200 * SHANNONS_PER_CKB,
+        Bytes::from(vec![1]),
+        absolute_epoch_since(20 + 42),
+    );
+    let creator_lock_script = prepare_creator_lock_script(context);
+    let outputs = vec![(
+        CellOutput::new_builder()
+            .capacity((200 * SHANNONS_PER_CKB).pack())
+            .lock(creator_lock_script)
+            .build(),
+        Bytes::new(),
+    )];
+    build_tx(
+        context,
+        vec![input],
+        outputs,
+        cell_deps,
+        Bytes::new(),
+        &sender_key,
+    )
+}
+
+#[test]
+fn test_sender_refund_after_amended_milestone_deadline_failed() {
+    let mut context = Context::default();
+    let tx = build_amended_refund_tx(&mut context, false);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_PROJECT_NOT_FAILED);
+}
+
+#[test]
+fn test_sender_refund_with_forged_amendment_success() {
+    let mut context = Context::default();
+    // the lock ignores the amendments not proposed by its own proposal cells
+    let tx = build_amended_refund_tx(&mut context, true);
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}